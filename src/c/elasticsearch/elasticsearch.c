@@ -22,20 +22,33 @@
 #include "rest/rest.h"
 #include "indexam/zdbam.h"
 
+#include "access/heapam.h"
+#include "access/htup_details.h"
 #include "access/transam.h"
 #include "access/xact.h"
 #include "catalog/index.h"
 #include "catalog/pg_collation.h"
+#include "catalog/pg_type.h"
 #include "commands/dbcommands.h"
+#include "executor/spi.h"
+#include "storage/bufmgr.h"
+#include "storage/ipc.h"
+#include "storage/lwlock.h"
+#include "storage/procarray.h"
+#include "storage/shmem.h"
 #include "utils/formatting.h"
+#include "utils/json.h"
 #include "utils/lsyscache.h"
+#include "utils/timestamp.h"
 
-/* an ES limit introduced around Elasticsearch v5 */
-#define MAX_DOCS_PER_REQUEST 10000
+/* how long we'll wait for the cluster to become healthy before building an index */
+#define ES_HEALTH_CHECK_TIMEOUT "5s"
+#define WRITE_BLOCK_CHECK_INTERVAL 25  /* how many _bulk requests between checks for index write blocks */
+#define MAX_SHARED_BULK_STATS 1024     /* how many indexes, across all databases, we keep shared bulk stats for */
+#define MAX_ACTIVE_BUILDS 64           /* how many index builds, across all databases, zdb.active_builds can show at once */
 
-#define ES_BULK_RESPONSE_FILTER "errors,items.*.error"
-#define ES_SEARCH_RESPONSE_FILTER "_scroll_id,_shards.failed,hits.total,hits.hits.fields.*,hits.hits._id,hits.hits._score,hits.hits.highlight.*"
-#define ES_SEARCH_RESPONSE_FILTER_NO_SCORE "_scroll_id,_shards.failed,hits.total,hits.hits.fields.*,hits.hits._id,hits.hits.highlight.*"
+#define ES_SEARCH_RESPONSE_FILTER "_scroll_id,_shards.failed,hits.total,hits.hits.fields.*,hits.hits._id,hits.hits._routing,hits.hits._score,hits.hits.highlight.*"
+#define ES_SEARCH_RESPONSE_FILTER_NO_SCORE "_scroll_id,_shards.failed,hits.total,hits.hits.fields.*,hits.hits._id,hits.hits._routing,hits.hits.highlight.*"
 
 #define validate_alias(indexRel) \
     do { \
@@ -44,6 +57,35 @@
     } while(0)
 
 extern bool zdb_ignore_visibility_guc;
+extern char *zdb_bulk_filter_path_guc;
+extern int  zdb_bulk_concurrency_per_node_guc;
+extern int  zdb_small_table_threshold_guc;
+extern int  zdb_check_node_versions_guc;
+extern int  zdb_batch_max_age_guc;
+extern int  zdb_scroll_size_guc;
+
+/* per-index ElasticsearchBulkStats for this session, allocated in TopMemoryContext */
+static HTAB *bulk_stats = NULL;
+
+/* per-index ElasticsearchBulkStats, and the index builds in progress, for every backend, when we're in shared_preload_libraries */
+typedef struct SharedBulkStatsEntry {
+	Oid                    databaseOid;
+	ElasticsearchBulkStats stats;
+} SharedBulkStatsEntry;
+
+typedef struct SharedBulkStats {
+	LWLock               *lock;
+	int                  nentries;
+	SharedBulkStatsEntry entries[MAX_SHARED_BULK_STATS];
+	ElasticsearchActiveBuild builds[MAX_ACTIVE_BUILDS];
+} SharedBulkStats;
+
+static SharedBulkStats     *shared_bulk_stats     = NULL;
+static shmem_startup_hook_type prev_shmem_startup_hook = NULL;
+
+/* the cluster we last counted data nodes for, and how many it had */
+static char *data_node_count_url = NULL;
+static int  data_node_count      = 0;
 
 static PostDataEntry *checkout_batch_pool(ElasticsearchBulkContext *context) {
 	int i;
@@ -99,29 +141,164 @@ static char *generate_uuid_index_name(Relation indexRel) {
 	return name;
 }
 
+/*
+ * Returns the "error.type" value from an Elasticsearch error response, or NULL if the
+ * response doesn't describe an error
+ */
+static const char *extract_error_type(StringInfo response) {
+	void *json;
+	void *error;
+
+	if (response->len == 0 || response->data[0] != '{')
+		return NULL;
+
+	json  = parse_json_object(response, CurrentMemoryContext);
+	error = get_json_object_object(json, "error", true);
+	if (error == NULL)
+		return NULL;
+
+	return get_json_object_string(error, "type", true);
+}
+
 char *ElasticsearchArbitraryRequest(Relation indexRel, char *method, char *endpoint, StringInfo postData) {
 	StringInfo request = makeStringInfo();
+	char       *url;
 
 	assert(method != NULL);
 
+	/* a GET can't change anything, so it can go to the 'search_url', if there is one */
+	url = strcmp(method, "GET") == 0 ? ZDBIndexOptionsGetSearchUrl(indexRel) : ZDBIndexOptionsGetUrl(indexRel);
+
 	if (endpoint[0] == '/') {
 		/* caller wants to directly query the cluster from the root */
-		appendStringInfo(request, "%s%s", ZDBIndexOptionsGetUrl(indexRel), endpoint + 1);
+		appendStringInfo(request, "%s%s", url, endpoint + 1);
 		return rest_call(method, request, postData, ZDBIndexOptionsGetCompressionLevel(indexRel))->data;
 	} else {
 		/* caller wants to query the index */
-		appendStringInfo(request, "%s%s/%s", ZDBIndexOptionsGetUrl(indexRel), ZDBIndexOptionsGetIndexName(indexRel),
-						 endpoint);
+		appendStringInfo(request, "%s%s/%s", url, ZDBIndexOptionsGetIndexName(indexRel), endpoint);
 		return rest_call(method, request, postData, ZDBIndexOptionsGetCompressionLevel(indexRel))->data;
 	}
 }
 
+/*
+ * Make sure the Elasticsearch cluster is reachable and at least "yellow" before we do anything destructive to it
+ */
+void ElasticsearchCheckClusterHealth(Relation indexRel) {
+	StringInfo request = makeStringInfo();
+	StringInfo response;
+	int64      response_code;
+	char       *url    = ZDBIndexOptionsGetUrl(indexRel);
+
+	appendStringInfo(request, "%s_cluster/health?wait_for_status=yellow&timeout=%s&filter_path=status,timed_out", url,
+					 ES_HEALTH_CHECK_TIMEOUT);
+	response = rest_call_unchecked("GET", request, NULL, ZDBIndexOptionsGetCompressionLevel(indexRel), &response_code);
+
+	if (response_code == 0) {
+		ereport(ERROR,
+				(errcode(ERRCODE_CONNECTION_FAILURE),
+						errmsg("unable to connect to Elasticsearch at '%s'", url),
+						errdetail("%s", response->data)));
+	} else if (response_code != 200) {
+		ereport(ERROR,
+				(errcode(ERRCODE_CONNECTION_FAILURE),
+						errmsg("Elasticsearch cluster at '%s' did not reach yellow health within %s", url,
+							   ES_HEALTH_CHECK_TIMEOUT),
+						errdetail("code=%ld, response=%s", response_code, response->data)));
+	}
+
+	freeStringInfo(response);
+	freeStringInfo(request);
+}
+
+/*
+ * During a rolling upgrade a cluster can briefly have nodes on different major versions, which can disagree
+ * about how the new index's mapping should behave.  If zdb.check_node_versions says to, complain about that
+ * before we build anything
+ */
+void ElasticsearchCheckNodeVersions(Relation indexRel) {
+	StringInfo request;
+	StringInfo response;
+	char       *url = ZDBIndexOptionsGetUrl(indexRel);
+
+	if (zdb_check_node_versions_guc == CHECK_NODE_VERSIONS_OFF)
+		return;
+
+	request = makeStringInfo();
+	appendStringInfo(request, "%s_nodes?filter_path=nodes.*.name,nodes.*.version", url);
+	response = rest_call("GET", request, NULL, ZDBIndexOptionsGetCompressionLevel(indexRel));
+	ElasticsearchCompareNodeVersions(url, response);
+
+	freeStringInfo(response);
+	freeStringInfo(request);
+}
+
+/*
+ * Compare the major versions of the nodes in a "GET _nodes" response from the cluster at 'url', and
+ * raise a WARNING or ERROR, as zdb.check_node_versions says to, if they're not all the same
+ */
+void ElasticsearchCompareNodeVersions(const char *url, StringInfo response) {
+	void                  *nodes;
+	JsonObjectKeyIterator itr;
+	const char            *firstName    = NULL;
+	const char            *firstVersion = NULL;
+
+	if (zdb_check_node_versions_guc == CHECK_NODE_VERSIONS_OFF)
+		return;
+
+	nodes = get_json_object_object(parse_json_object(response, CurrentMemoryContext), "nodes", true);
+
+	for (itr = nodes == NULL ? NULL : get_json_object_key_iterator(nodes);
+		 itr != NULL; itr = get_next_from_json_object_iterator(itr)) {
+		void       *node    = get_json_object_object(nodes, (char *) get_key_from_json_object_iterator(itr), false);
+		const char *name    = get_json_object_string(node, "name", true);
+		const char *version = get_json_object_string(node, "version", false);
+
+		if (name == NULL)
+			name = get_key_from_json_object_iterator(itr);
+
+		if (firstVersion == NULL) {
+			firstName    = name;
+			firstVersion = version;
+		} else if (atoi(version) != atoi(firstVersion)) {
+			ereport(zdb_check_node_versions_guc == CHECK_NODE_VERSIONS_ERROR ? ERROR : WARNING,
+					(errcode(ERRCODE_OBJECT_NOT_IN_PREREQUISITE_STATE),
+							errmsg("Elasticsearch cluster at '%s' has nodes running different major versions", url),
+							errdetail("node '%s' is running %s and node '%s' is running %s", firstName, firstVersion,
+									  name, version),
+							errhint("Wait for the cluster's upgrade to finish, or change zdb.check_node_versions")));
+			break;
+		}
+	}
+}
+
+/*
+ * The members of the index's 'index_settings' json object, with a leading comma, so they can be added to
+ * the end of the "settings" object we create the index with.  Its validator already made sure it's an
+ * object and doesn't contain any settings we generate ourselves
+ */
+static char *extra_index_settings(Relation indexRel) {
+	char *settings = ZDBIndexOptionsGetIndexSettings(indexRel);
+	char *start;
+	char *end;
+
+	if (settings == NULL)
+		return "";
+
+	start = strchr(settings, '{') + 1;
+	end   = strrchr(settings, '}');
+	if (strspn(start, " \t\r\n") >= (size_t) (end - start))
+		return "";  /* it's an empty object */
+
+	return psprintf(", %.*s", (int) (end - start), start);
+}
+
 char *ElasticsearchCreateIndex(Relation heapRel, Relation indexRel, TupleDesc tupdesc, char *aliasName) {
 	char       *indexName = generate_uuid_index_name(indexRel);
 	StringInfo request    = makeStringInfo();
 	StringInfo settings   = makeStringInfo();
-	StringInfo mapping    = generate_mapping(heapRel, tupdesc);
+	StringInfo mapping    = generate_mapping(heapRel, indexRel, tupdesc);
 	StringInfo response;
+	int64      response_code;
 
 	if (ZDBIndexOptionsGetIndexName(indexRel) != NULL) {
 		elog(LOG, "[zombodb] Reusing index with name '%s'", ZDBIndexOptionsGetIndexName(indexRel));
@@ -142,7 +319,7 @@ char *ElasticsearchCreateIndex(Relation heapRel, Relation indexRel, TupleDesc tu
 							   "         \"tokenizer\" : { %s },"
 							   "         \"analyzer\": { %s },"
 							   "         \"normalizer\": { %s }"
-							   "      }"
+							   "      }%s"
 							   "   },"
 							   "   \"mappings\": {"
 							   "      \"%s\": { "
@@ -184,17 +361,36 @@ char *ElasticsearchCreateIndex(Relation heapRel, Relation indexRel, TupleDesc tu
 					 lookup_analysis_thing(CurrentMemoryContext, "tokenizers"),
 					 lookup_analysis_thing(CurrentMemoryContext, "analyzers"),
 					 lookup_analysis_thing(CurrentMemoryContext, "normalizers"),
+					 extra_index_settings(indexRel),
 					 ZDBIndexOptionsGetTypeName(indexRel),
 					 mapping->data,
 					 aliasName);
 
 	appendStringInfo(request, "%s%s", ZDBIndexOptionsGetUrl(indexRel), indexName);
 
-	/* first, delete the old index */
-	ElasticsearchDeleteIndex(indexRel);
+	/* first, delete the old index, if there is one.  A newly-generated name won't have one */
+	if (ZDBIndexOptionsGetIndexName(indexRel) != NULL && ElasticsearchIndexExists(indexRel))
+		ElasticsearchDeleteIndex(indexRel);
 
 	/* secondly, create the new index */
-	response = rest_call("PUT", request, settings, ZDBIndexOptionsGetCompressionLevel(indexRel));
+	response = rest_call_unchecked("PUT", request, settings, ZDBIndexOptionsGetCompressionLevel(indexRel),
+								   &response_code);
+	if (response_code < 200 || response_code >= 300) {
+		const char *type = extract_error_type(response);
+
+		if (type != NULL && strcmp("resource_already_exists_exception", type) == 0) {
+			/* somebody else created it between our DELETE and PUT */
+			ereport(ERROR,
+					(errcode(ERRCODE_DUPLICATE_OBJECT),
+							errmsg("Elasticsearch index '%s' already exists", indexName),
+							errhint("Another session is probably building an index with the same 'uuid'.  Wait for it to finish and try again")));
+		}
+
+		ereport(ERROR,
+				(errcode(ERRCODE_IO_ERROR),
+						errmsg("unexpected http response code from remote server.  code=%ld, response=%s",
+							   response_code, response->data)));
+	}
 
 	freeStringInfo(mapping);
 	freeStringInfo(settings);
@@ -204,6 +400,26 @@ char *ElasticsearchCreateIndex(Relation heapRel, Relation indexRel, TupleDesc tu
 	return indexName;
 }
 
+bool ElasticsearchIndexExists(Relation indexRel) {
+	StringInfo request = makeStringInfo();
+	StringInfo response;
+	int64      response_code;
+
+	appendStringInfo(request, "%s%s", ZDBIndexOptionsGetUrl(indexRel), ZDBIndexOptionsGetIndexName(indexRel));
+	response = rest_call_unchecked("HEAD", request, NULL, ZDBIndexOptionsGetCompressionLevel(indexRel),
+								   &response_code);
+	if (response_code != 200 && response_code != 404)
+		ereport(ERROR,
+				(errcode(ERRCODE_IO_ERROR),
+						errmsg("unexpected http response code from remote server.  code=%ld, response=%s",
+							   response_code, response->data)));
+
+	freeStringInfo(request);
+	freeStringInfo(response);
+
+	return response_code == 200;
+}
+
 void ElasticsearchDeleteIndex(Relation indexRel) {
 	StringInfo request = makeStringInfo();
 	StringInfo response;
@@ -227,6 +443,16 @@ void ElasticsearchDeleteIndexDirect(char *index_url) {
 	freeStringInfo(response);
 }
 
+/*
+ * With refresh_interval='wait_for', writes wait for Elasticsearch's own periodic refresh, so the index
+ * needs to actually have one
+ */
+static const char *es_refresh_interval(Relation indexRel) {
+	char *refreshInterval = ZDBIndexOptionsGetRefreshInterval(indexRel);
+
+	return strcmp("wait_for", refreshInterval) == 0 ? "1s" : refreshInterval;
+}
+
 void ElasticsearchFinalizeIndexCreation(Relation indexRel) {
 	StringInfo request  = makeStringInfo();
 	StringInfo settings = makeStringInfo();
@@ -239,7 +465,7 @@ void ElasticsearchFinalizeIndexCreation(Relation indexRel) {
 							   "      \"number_of_replicas\": %d"
 							   "   }"
 							   "}",
-					 ZDBIndexOptionsGetRefreshInterval(indexRel),
+					 es_refresh_interval(indexRel),
 					 ZDBIndexOptionsGetNumberOfReplicas(indexRel));
 
 	appendStringInfo(request, "%s%s/_settings", ZDBIndexOptionsGetUrl(indexRel), ZDBIndexOptionsGetIndexName(indexRel));
@@ -287,8 +513,9 @@ void ElasticsearchUpdateSettings(Relation indexRel, char *oldAlias, char *newAli
 void ElasticsearchPutMapping(Relation heapRel, Relation indexRel, TupleDesc tupdesc) {
 	StringInfo request  = makeStringInfo();
 	StringInfo settings = makeStringInfo();
-	StringInfo mapping  = generate_mapping(heapRel, tupdesc);
+	StringInfo mapping  = generate_mapping(heapRel, indexRel, tupdesc);
 	StringInfo response;
+	int64      response_code;
 
 	appendStringInfo(settings, ""
 							   "{"
@@ -298,7 +525,16 @@ void ElasticsearchPutMapping(Relation heapRel, Relation indexRel, TupleDesc tupd
 
 	appendStringInfo(request, "%s%s/_mapping/doc", ZDBIndexOptionsGetUrl(indexRel),
 					 ZDBIndexOptionsGetIndexName(indexRel));
-	response = rest_call("PUT", request, settings, ZDBIndexOptionsGetCompressionLevel(indexRel));
+	response = rest_call_unchecked("PUT", request, settings, ZDBIndexOptionsGetCompressionLevel(indexRel),
+								   &response_code);
+
+	/* Elasticsearch can add new fields to a mapping, but it can't change the ones it already has */
+	if (response_code != 200)
+		ereport(ERROR,
+				(errcode(ERRCODE_INVALID_OBJECT_DEFINITION),
+						errmsg("Elasticsearch rejected the new mapping for index '%s'", RelationGetRelationName(indexRel)),
+						errdetail("code=%ld, response=%s", response_code, response->data),
+						errhint("Changing the mapping of an existing field requires a REINDEX")));
 
 	freeStringInfo(settings);
 	freeStringInfo(request);
@@ -306,7 +542,382 @@ void ElasticsearchPutMapping(Relation heapRel, Relation indexRel, TupleDesc tupd
 }
 
 
-ElasticsearchBulkContext *ElasticsearchStartBulkProcess(Relation indexRel, char *indexName, TupleDesc tupdesc, bool ignore_version_conflicts) {
+static void bulk_stats_shmem_startup(void) {
+	bool found;
+
+	if (prev_shmem_startup_hook)
+		prev_shmem_startup_hook();
+
+	LWLockAcquire(AddinShmemInitLock, LW_EXCLUSIVE);
+	shared_bulk_stats = ShmemInitStruct("zombodb bulk stats", sizeof(SharedBulkStats), &found);
+	if (!found) {
+		memset(shared_bulk_stats, 0, sizeof(SharedBulkStats));
+		shared_bulk_stats->lock = &(GetNamedLWLockTranche("zombodb bulk stats"))->lock;
+	}
+	LWLockRelease(AddinShmemInitLock);
+}
+
+/*
+ * Ask for the shared memory zdb.bulk_stats(shared => true) and zdb.active_builds read from.  Only possible when
+ * we're loaded via shared_preload_libraries -- otherwise bulk stats are only ever per-backend
+ */
+void ElasticsearchBulkStatsShmemRequest(void) {
+	if (!process_shared_preload_libraries_in_progress)
+		return;
+
+	RequestAddinShmemSpace(MAXALIGN(sizeof(SharedBulkStats)));
+	RequestNamedLWLockTranche("zombodb bulk stats", 1);
+
+	prev_shmem_startup_hook = shmem_startup_hook;
+	shmem_startup_hook      = bulk_stats_shmem_startup;
+}
+
+/*
+ * Returns a copy of every index's shared bulk stats, for the current database
+ */
+static void require_shared_memory(const char *what) {
+	if (shared_bulk_stats == NULL)
+		ereport(ERROR,
+				(errcode(ERRCODE_OBJECT_NOT_IN_PREREQUISITE_STATE),
+						errmsg("%s only available when zombodb is loaded via shared_preload_libraries", what),
+						errhint("Add 'zombodb.so' to shared_preload_libraries in postgresql.conf and restart Postgres")));
+}
+
+List *ElasticsearchGetSharedBulkStats(void) {
+	List *entries = NIL;
+	int  i;
+
+	require_shared_memory("bulk stats across all sessions are");
+
+	LWLockAcquire(shared_bulk_stats->lock, LW_SHARED);
+	for (i = 0; i < shared_bulk_stats->nentries; i++) {
+		SharedBulkStatsEntry *entry = &shared_bulk_stats->entries[i];
+
+		if (entry->databaseOid == MyDatabaseId) {
+			ElasticsearchBulkStats *copy = palloc(sizeof(ElasticsearchBulkStats));
+
+			memcpy(copy, &entry->stats, sizeof(ElasticsearchBulkStats));
+			entries = lappend(entries, copy);
+		}
+	}
+	LWLockRelease(shared_bulk_stats->lock);
+
+	return entries;
+}
+
+static void bulk_stats_add(ElasticsearchBulkStats *into, ElasticsearchBulkStats *delta) {
+	into->requests += delta->requests;
+	into->documents += delta->documents;
+	into->versionConflicts += delta->versionConflicts;
+	into->deadLetters += delta->deadLetters;
+	into->queueDepth += delta->queueDepth;
+	latency_histogram_merge(&into->latencies, &delta->latencies);
+}
+
+static void publish_shared_bulk_stats(Oid indexRelid, ElasticsearchBulkStats *delta) {
+	SharedBulkStatsEntry *entry = NULL;
+	int                  i;
+
+	if (shared_bulk_stats == NULL)
+		return;
+
+	LWLockAcquire(shared_bulk_stats->lock, LW_EXCLUSIVE);
+	for (i = 0; i < shared_bulk_stats->nentries; i++) {
+		if (shared_bulk_stats->entries[i].databaseOid == MyDatabaseId &&
+			shared_bulk_stats->entries[i].stats.indexRelid == indexRelid) {
+			entry = &shared_bulk_stats->entries[i];
+			break;
+		}
+	}
+
+	if (entry == NULL && shared_bulk_stats->nentries < MAX_SHARED_BULK_STATS) {
+		entry = &shared_bulk_stats->entries[shared_bulk_stats->nentries++];
+		memset(entry, 0, sizeof(SharedBulkStatsEntry));
+		entry->databaseOid      = MyDatabaseId;
+		entry->stats.indexRelid = indexRelid;
+	}
+
+	/* once we're out of room, indexes we haven't seen before only have per-backend stats */
+	if (entry != NULL)
+		bulk_stats_add(&entry->stats, delta);
+	LWLockRelease(shared_bulk_stats->lock);
+}
+
+/*
+ * Forget a dropped index's bulk stats, both this session's and its shared ones, so that its shared
+ * entry can be reused, and so that an index that's later given the same oid starts from zero
+ */
+void ElasticsearchForgetBulkStats(Oid indexRelid) {
+	int i;
+
+	if (bulk_stats != NULL)
+		hash_search(bulk_stats, &indexRelid, HASH_REMOVE, NULL);
+
+	if (shared_bulk_stats == NULL)
+		return;
+
+	LWLockAcquire(shared_bulk_stats->lock, LW_EXCLUSIVE);
+	for (i = 0; i < shared_bulk_stats->nentries; i++) {
+		if (shared_bulk_stats->entries[i].databaseOid == MyDatabaseId &&
+			shared_bulk_stats->entries[i].stats.indexRelid == indexRelid) {
+			/* the last entry takes its place */
+			shared_bulk_stats->entries[i] = shared_bulk_stats->entries[--shared_bulk_stats->nentries];
+			break;
+		}
+	}
+	LWLockRelease(shared_bulk_stats->lock);
+}
+
+/*
+ * Returns a copy of every index build in progress in the current database
+ */
+List *ElasticsearchGetActiveBuilds(void) {
+	List *builds = NIL;
+	int  i;
+
+	require_shared_memory("active builds are");
+
+	LWLockAcquire(shared_bulk_stats->lock, LW_SHARED);
+	for (i = 0; i < MAX_ACTIVE_BUILDS; i++) {
+		ElasticsearchActiveBuild *build = &shared_bulk_stats->builds[i];
+
+		/* a backend that died mid-build never cleared its slot */
+		if (build->pid != 0 && build->databaseOid == MyDatabaseId && BackendPidGetProc(build->pid) != NULL) {
+			ElasticsearchActiveBuild *copy = palloc(sizeof(ElasticsearchActiveBuild));
+
+			memcpy(copy, build, sizeof(ElasticsearchActiveBuild));
+			builds = lappend(builds, copy);
+		}
+	}
+	LWLockRelease(shared_bulk_stats->lock);
+
+	return builds;
+}
+
+/*
+ * Record how far along this backend's build of the context's index is, claiming a slot for it
+ * the first time.  Once they're all taken, other builds just aren't shown
+ */
+static void publish_active_build(ElasticsearchBulkContext *context) {
+	ElasticsearchActiveBuild *build = NULL;
+	int                      i;
+
+	if (shared_bulk_stats == NULL || !context->isBuild)
+		return;
+
+	LWLockAcquire(shared_bulk_stats->lock, LW_EXCLUSIVE);
+	for (i = 0; i < MAX_ACTIVE_BUILDS; i++) {
+		if (shared_bulk_stats->builds[i].pid == MyProcPid &&
+			shared_bulk_stats->builds[i].indexRelid == context->indexRelid) {
+			build = &shared_bulk_stats->builds[i];
+			break;
+		}
+	}
+
+	for (i = 0; build == NULL && i < MAX_ACTIVE_BUILDS; i++) {
+		ElasticsearchActiveBuild *slot = &shared_bulk_stats->builds[i];
+
+		if (slot->pid == 0 || BackendPidGetProc(slot->pid) == NULL) {
+			build = slot;
+			memset(build, 0, sizeof(ElasticsearchActiveBuild));
+			build->pid         = MyProcPid;
+			build->databaseOid = MyDatabaseId;
+			build->indexRelid  = context->indexRelid;
+			build->started     = GetCurrentTimestamp();
+		}
+	}
+
+	if (build != NULL) {
+		build->rows            = context->ntotal;
+		build->requests        = context->nrequests;
+		build->inflight        = context->bulkConcurrency - context->rest->available;
+		build->bulkConcurrency = context->bulkConcurrency;
+	}
+	LWLockRelease(shared_bulk_stats->lock);
+}
+
+/*
+ * Free the slots of this backend's builds, once they've finished or their transaction aborted
+ */
+void ElasticsearchForgetActiveBuilds(void) {
+	int i;
+
+	if (shared_bulk_stats == NULL)
+		return;
+
+	LWLockAcquire(shared_bulk_stats->lock, LW_EXCLUSIVE);
+	for (i = 0; i < MAX_ACTIVE_BUILDS; i++) {
+		if (shared_bulk_stats->builds[i].pid == MyProcPid)
+			memset(&shared_bulk_stats->builds[i], 0, sizeof(ElasticsearchActiveBuild));
+	}
+	LWLockRelease(shared_bulk_stats->lock);
+}
+
+HTAB *ElasticsearchGetBulkStats(void) {
+	if (bulk_stats == NULL) {
+		HASHCTL ctl;
+
+		memset(&ctl, 0, sizeof(HASHCTL));
+		ctl.hcxt      = TopMemoryContext;
+		ctl.keysize   = sizeof(Oid);
+		ctl.entrysize = sizeof(ElasticsearchBulkStats);
+
+		bulk_stats = hash_create("zdb bulk stats", 32, &ctl, HASH_ELEM | HASH_BLOBS | HASH_CONTEXT);
+	}
+
+	return bulk_stats;
+}
+
+/*
+ * Add to this index's stats for this session, and to its shared stats, if we have them
+ */
+static void record_bulk_stats(ElasticsearchBulkContext *context, ElasticsearchBulkStats *delta) {
+	ElasticsearchBulkStats *stats;
+	bool                   found;
+
+	stats = hash_search(ElasticsearchGetBulkStats(), &context->indexRelid, HASH_ENTER, &found);
+	if (!found) {
+		memset(stats, 0, sizeof(ElasticsearchBulkStats));
+		stats->indexRelid = context->indexRelid;
+	}
+
+	bulk_stats_add(stats, delta);
+	publish_shared_bulk_stats(context->indexRelid, delta);
+}
+
+/*
+ * Work out bulk_concurrency for an index that has it set to zero:  zdb.bulk_concurrency_per_node for
+ * each data node in the cluster.  The node count is only requested once per backend for any given url
+ */
+static int auto_bulk_concurrency(char *url, int compressionLevel) {
+	if (data_node_count_url == NULL || strcmp(data_node_count_url, url) != 0) {
+		StringInfo request = makeStringInfo();
+		StringInfo response;
+		void       *nodes;
+
+		appendStringInfo(request, "%s_nodes/data:true?filter_path=_nodes.total", rest_choose_url(url));
+		response = rest_call("GET", request, NULL, compressionLevel);
+		nodes    = get_json_object_object(parse_json_object(response, CurrentMemoryContext), "_nodes", true);
+
+		data_node_count = nodes == NULL ? 0 : (int) get_json_object_uint64(nodes, "total", true);
+		if (data_node_count_url != NULL)
+			pfree(data_node_count_url);
+		data_node_count_url = MemoryContextStrdup(TopMemoryContext, url);
+
+		freeStringInfo(response);
+		freeStringInfo(request);
+	}
+
+	return Min(Max(data_node_count, 1) * zdb_bulk_concurrency_per_node_guc, MAX_BULK_CONCURRENCY);
+}
+
+/*
+ * Limit how many shards a _search request may query at once, if the index's 'max_concurrent_shard_requests'
+ * option says to.  Otherwise Elasticsearch uses its own default.  The finished url is logged at DEBUG1
+ */
+static void finish_search_request(StringInfo request, Relation indexRel) {
+	int max = ZDBIndexOptionsGetMaxConcurrentShardRequests(indexRel);
+
+	if (max > 0)
+		appendStringInfo(request, "&max_concurrent_shard_requests=%d", max);
+
+	elog(DEBUG1, "[zombodb] searching %s:  POST %s", RelationGetRelationName(indexRel), request->data);
+}
+
+/*
+ * Estimate how many rows the table being indexed has, the way the planner does, from its current
+ * size and the density pg_class last recorded.  A table that's never been vacuumed or analyzed is
+ * assumed to be as dense as possible, so that we don't mistake a large one for a small one
+ */
+static double estimate_heap_rows(Relation indexRel) {
+	Relation    heapRel  = relation_open(IndexGetRelation(RelationGetRelid(indexRel), false), NoLock);
+	BlockNumber curpages = RelationGetNumberOfBlocks(heapRel);
+	double      density;
+
+	if (heapRel->rd_rel->relpages > 0)
+		density = heapRel->rd_rel->reltuples / heapRel->rd_rel->relpages;
+	else
+		density = MaxHeapTuplesPerPage;
+
+	relation_close(heapRel, NoLock);
+	return density * curpages;
+}
+
+/*
+ * Assemble the painless script used to set zdb_xmax/zdb_cmax, appending the index's 'update_script'
+ * snippet, if any, and merging its 'update_script_params' in with ours.
+ *
+ * Setting zdb_xmax/zdb_cmax is naturally idempotent, but the snippet might not be, and a _bulk request
+ * can be sent again after Elasticsearch already applied some of it (such as when the connection drops
+ * and we fail over to another host).  A document that already has this XMAX and CMAX has had this very
+ * update applied, so the snippet only runs when it doesn't.  Snippets can't reference anything named
+ * zdb_, so our local variable can't collide with one of theirs
+ */
+static void make_update_script(ElasticsearchBulkContext *context, char *snippet, char *params) {
+	StringInfo source = makeStringInfo();
+	StringInfo quoted = makeStringInfo();
+
+	if (snippet != NULL)
+		appendStringInfoString(source, "boolean zdb_applied=ctx._source.zdb_xmax!=null&&ctx._source.zdb_cmax!=null"
+									   "&&((Number)ctx._source.zdb_xmax).longValue()==((Number)params.XMAX).longValue()"
+									   "&&((Number)ctx._source.zdb_cmax).longValue()==((Number)params.CMAX).longValue();");
+	appendStringInfoString(source, "ctx._source.zdb_cmax=params.CMAX;ctx._source.zdb_xmax=params.XMAX;");
+	if (snippet != NULL)
+		appendStringInfo(source, "if(!zdb_applied){%s}", snippet);
+	escape_json(quoted, source->data);
+	context->updateScript = quoted->data;
+
+	context->updateScriptParams = "";
+	if (params != NULL) {
+		Jsonb *jb    = DatumGetJsonbP(DirectFunctionCall1(jsonb_in, CStringGetDatum(params)));
+		char  *json  = JsonbToCString(NULL, &jb->root, VARSIZE(jb));
+		int   len    = strlen(json);
+
+		/* strip the object's braces so its keys can sit alongside CMAX and XMAX */
+		if (len > 2)
+			context->updateScriptParams = psprintf(",%.*s", len - 2, json + 1);
+	}
+
+	pfree(source->data);
+}
+
+/*
+ * A build can hold bulk_concurrency+1 batches of batch_size bytes in memory at once.  When the index's
+ * bulk_concurrency is automatic, keep that within maintenance_work_mem:  first by lowering the concurrency,
+ * and then, if one request at a time still doesn't fit and batch_size wasn't given either, by lowering
+ * the batch size.  Options the index sets explicitly are never changed, only warned about
+ */
+static void bound_build_memory(ElasticsearchBulkContext *context, Relation indexRel, bool autoConcurrency) {
+	int64 budget            = (int64) maintenance_work_mem * 1024L;
+	int   concurrency       = context->bulkConcurrency;
+	int   batchSize         = context->batchSize;
+	bool  explicitBatchSize = index_option_is_set(indexRel, "batch_size");
+
+	if ((int64) (concurrency + 1) * batchSize <= budget)
+		return;
+
+	if (autoConcurrency) {
+		concurrency = (int) Max(budget / batchSize - 1, 1);
+		if ((int64) (concurrency + 1) * batchSize > budget && !explicitBatchSize)
+			batchSize = (int) Max(budget / (concurrency + 1), 1024);
+
+		elog(ZDB_LOG_LEVEL,
+			 "[zombodb] building %s with bulk_concurrency=%d and batch_size=%d to fit within maintenance_work_mem of %dkB",
+			 context->pgIndexName, concurrency, batchSize, maintenance_work_mem);
+
+		context->bulkConcurrency = concurrency;
+		context->batchSize       = batchSize;
+	}
+
+	if ((int64) (concurrency + 1) * batchSize > budget &&
+		(explicitBatchSize || (!autoConcurrency && index_option_is_set(indexRel, "bulk_concurrency"))))
+		ereport(WARNING,
+				(errmsg("[zombodb] building %s can use up to %ldkB, more than maintenance_work_mem of %dkB",
+						context->pgIndexName, (int64) (concurrency + 1) * batchSize / 1024, maintenance_work_mem),
+						errhint("Raise maintenance_work_mem, or lower the index's bulk_concurrency or batch_size")));
+}
+
+ElasticsearchBulkContext *ElasticsearchStartBulkProcess(Relation indexRel, char *indexName, TupleDesc tupdesc, bool ignore_version_conflicts, bool is_build) {
 	ElasticsearchBulkContext *context = palloc0(sizeof(ElasticsearchBulkContext));
 	int                      i;
 
@@ -319,16 +930,35 @@ ElasticsearchBulkContext *ElasticsearchStartBulkProcess(Relation indexRel, char
 		}
 	}
 
-	context->url                    = pstrdup(ZDBIndexOptionsGetUrl(indexRel));
+	context->url                    = pstrdup(ZDBIndexOptionsGetUrls(indexRel));
 	context->pgIndexName            = pstrdup(RelationGetRelationName(indexRel));
 	context->esIndexName            = pstrdup(indexName);
 	context->typeName               = pstrdup(ZDBIndexOptionsGetTypeName(indexRel));
 	context->batchSize              = ZDBIndexOptionsGetBatchSize(indexRel);
+	context->maxDocumentSize        = ZDBIndexOptionsGetMaxDocumentSize(indexRel);
 	context->bulkConcurrency        = ZDBIndexOptionsGetBulkConcurrency(indexRel);
+	if (context->bulkConcurrency == 0) {
+		if (is_build && estimate_heap_rows(indexRel) < zdb_small_table_threshold_guc)
+			context->bulkConcurrency = 1;    /* not worth the memory, or asking how many data nodes there are */
+		else
+			context->bulkConcurrency = auto_bulk_concurrency(context->url, ZDBIndexOptionsGetCompressionLevel(indexRel));
+	}
 	context->compressionLevel       = ZDBIndexOptionsGetCompressionLevel(indexRel);
 	context->shouldRefresh          = strcmp("-1", ZDBIndexOptionsGetRefreshInterval(indexRel)) == 0;
+	context->waitForRefresh         = strcmp("wait_for", ZDBIndexOptionsGetRefreshInterval(indexRel)) == 0;
 	context->ignoreVersionConflicts = ignore_version_conflicts;
-	context->rest                   = rest_multi_init(context->bulkConcurrency, ignore_version_conflicts);
+	context->skipErrors             = ZDBIndexOptionsGetSkipErrors(indexRel);
+	context->isBuild                = is_build;
+	context->indexRelid             = RelationGetRelid(indexRel);
+	context->routing                = ZDBIndexOptionsGetRouting(indexRel) ? pstrdup(ZDBIndexOptionsGetRouting(indexRel)) : NULL;
+	context->indexAction            = pstrdup(ZDBIndexOptionsGetIndexAction(indexRel));
+	context->bulkPrimaryTimeout     = ZDBIndexOptionsGetBulkPrimaryTimeout(indexRel) ? pstrdup(ZDBIndexOptionsGetBulkPrimaryTimeout(indexRel)) : NULL;
+	context->activeShardCount       = ZDBIndexOptionsGetWaitForActiveShards(indexRel) ? pstrdup(ZDBIndexOptionsGetWaitForActiveShards(indexRel)) : NULL;
+	make_update_script(context, ZDBIndexOptionsGetUpdateScript(indexRel), ZDBIndexOptionsGetUpdateScriptParams(indexRel));
+	if (is_build)
+		bound_build_memory(context, indexRel, ZDBIndexOptionsGetBulkConcurrency(indexRel) == 0);
+	context->rest                   = rest_multi_init(context->bulkConcurrency, ignore_version_conflicts,
+													  context->skipErrors);
 
 	for (i = 0; i < context->bulkConcurrency + 1; i++)
 		context->pool[i] = makeStringInfo();
@@ -336,6 +966,7 @@ ElasticsearchBulkContext *ElasticsearchStartBulkProcess(Relation indexRel, char
 	context->rest->pool          = context->pool;
 	context->current             = checkout_batch_pool(context);
 	context->waitForActiveShards = false;
+	publish_active_build(context);
 
 	if (tupdesc != NULL) {
 	    /*
@@ -345,7 +976,14 @@ ElasticsearchBulkContext *ElasticsearchStartBulkProcess(Relation indexRel, char
 	     * These are used per-row, when we convert them to json for indexing
 	     */
 		context->tupdesc         = CreateTupleDescCopy(tupdesc);
-		context->jsonConversions = build_json_conversions(tupdesc);
+		context->jsonConversions = build_json_conversions(indexRel, context->tupdesc);
+
+		/* look up the transform function once, rather than for every row */
+		if (ZDBIndexOptionsGetTransformFunction(indexRel) != NULL) {
+			context->transformFunction = palloc0(sizeof(FmgrInfo));
+			fmgr_info(lookup_transform_function(ZDBIndexOptionsGetTransformFunction(indexRel)),
+					  context->transformFunction);
+		}
 
 		/*
 		 * look for fields of type ::json in the tuple and note the existence
@@ -355,6 +993,12 @@ ElasticsearchBulkContext *ElasticsearchStartBulkProcess(Relation indexRel, char
 		 */
 		context->containsJson      = tuple_desc_contains_json(tupdesc);
 		context->containsJsonIsSet = true;
+
+		if (context->routing != NULL && SPI_fnumber(tupdesc, context->routing) <= 0) {
+			ereport(ERROR,
+					(errcode(ERRCODE_UNDEFINED_COLUMN),
+							errmsg("'routing' index option references unknown column '%s'", context->routing)));
+		}
 	}
 
 	return context;
@@ -394,15 +1038,150 @@ static inline void remember_curr_xid(ElasticsearchBulkContext *context) {
 	}
 }
 
+/*
+ * The _bulk endpoint for this context, with its response filtered by zdb.bulk_filter_path
+ */
+static void make_bulk_endpoint(ElasticsearchBulkContext *context, StringInfo request) {
+	appendStringInfo(request, "%s%s/%s/_bulk?filter_path=%s", rest_choose_url(context->url), context->esIndexName,
+					 context->typeName, zdb_bulk_filter_path_guc);
+
+	/*
+	 * we need to know which documents failed, to skip or retry them, and which were version conflicts, to
+	 * count them.  Every item has an _id, so this also keeps the items in the same positions as the
+	 * documents in the request
+	 */
+	appendStringInfoString(request, ",items.*._id,items.*.status");
+
+	if (context->bulkPrimaryTimeout != NULL)
+		appendStringInfo(request, "&timeout=%s", context->bulkPrimaryTimeout);
+
+	/* batches that delete rows already wait for all of them */
+	if (context->activeShardCount != NULL && !context->waitForActiveShards)
+		appendStringInfo(request, "&wait_for_active_shards=%s", context->activeShardCount);
+
+	/* each request returns once a scheduled refresh has made its changes visible */
+	if (context->waitForRefresh)
+		appendStringInfo(request, "&refresh=wait_for");
+}
+
+/*
+ * Write any documents Elasticsearch rejected, while using on_error='skip', to zdb.dead_letters
+ */
+static void record_dead_letters(ElasticsearchBulkContext *context) {
+	Oid                    argtypes[3] = {REGCLASSOID, TIDOID, TEXTOID};
+	ListCell               *lc;
+	ElasticsearchBulkStats delta;
+
+	if (context->rest->dead_letters == NIL)
+		return;
+
+	SPI_connect();
+	foreach (lc, context->rest->dead_letters) {
+		DeadLetter      *letter  = lfirst(lc);
+		Datum           values[3];
+		char            nulls[3] = {' ', 'n', ' '};
+		ItemPointerData ctid;
+		char            *endptr;
+
+		values[0] = ObjectIdGetDatum(context->indexRelid);
+		values[2] = CStringGetTextDatum(letter->reason);
+
+		if (letter->id != NULL) {
+			uint64 ctidAs64bits = strtoull(letter->id, &endptr, 10);
+
+			/* documents not backed by a heap tuple, such as zdb_aborted_xids, don't have a numeric _id */
+			if (*endptr == '\0') {
+				ItemPointerSet(&ctid, (BlockNumber) (ctidAs64bits >> 32), (OffsetNumber) ctidAs64bits);
+				values[1] = PointerGetDatum(&ctid);
+				nulls[1]  = ' ';
+			}
+		}
+
+		if (SPI_execute_with_args("INSERT INTO zdb.dead_letters (index, ctid, reason) VALUES ($1, $2, $3);",
+								  3, argtypes, values, nulls, false, 0) != SPI_OK_INSERT)
+			elog(ERROR, "failed to record a dead letter for index '%s'", context->pgIndexName);
+
+		elog(ZDB_LOG_LEVEL, "[zombodb] skipped document %s in %s: %s",
+			 letter->id ? letter->id : "(unknown)", context->pgIndexName, letter->reason);
+	}
+	SPI_finish();
+
+	memset(&delta, 0, sizeof(ElasticsearchBulkStats));
+	delta.deadLetters = list_length(context->rest->dead_letters);
+	record_bulk_stats(context, &delta);
+
+	list_free_deep(context->rest->dead_letters);
+	context->rest->dead_letters = NIL;
+}
+
+/*
+ * has the current batch been accumulating rows for longer than zdb.batch_max_age?
+ */
+bool ElasticsearchBulkBatchIsTooOld(ElasticsearchBulkContext *context) {
+	return zdb_batch_max_age_guc > 0 && context->nrows > 0 &&
+		   TimestampDifferenceExceeds(context->batchStarted, GetCurrentTimestamp(), zdb_batch_max_age_guc);
+}
+
+/*
+ * Elasticsearch blocks writes to an index when the cluster passes its flood-stage disk watermark, or when
+ * someone sets one of these by hand.  Better to stop now than to keep sending batches it'll only reject
+ */
+static void check_write_blocks(ElasticsearchBulkContext *context) {
+	static char *blocks[] = {"read_only", "read_only_allow_delete", "write"};
+	StringInfo  request   = makeStringInfo();
+	StringInfo  response;
+	void        *json;
+	int         i;
+
+	appendStringInfo(request, "%s%s/_settings?filter_path=*.settings.index.blocks", rest_choose_url(context->url),
+					 context->esIndexName);
+	response = rest_call("GET", request, NULL, context->compressionLevel);
+	json     = parse_json_object(response, CurrentMemoryContext);
+
+	/* the response is keyed by the concrete index name, which is ours */
+	json = json ? get_json_object_object(json, context->esIndexName, true) : NULL;
+	json = json ? get_json_object_object(json, "settings", true) : NULL;
+	json = json ? get_json_object_object(json, "index", true) : NULL;
+	json = json ? get_json_object_object(json, "blocks", true) : NULL;
+
+	for (i = 0; json != NULL && i < lengthof(blocks); i++) {
+		const char *value = get_json_object_string(json, blocks[i], true);
+
+		if (value != NULL && strcmp("true", value) == 0)
+			ereport(ERROR,
+					(errcode(ERRCODE_READ_ONLY_SQL_TRANSACTION),
+							errmsg("Elasticsearch index for '%s' no longer accepts writes:  index.blocks.%s is set",
+								   context->pgIndexName, blocks[i]),
+							errdetail("The Elasticsearch index is '%s'.", context->esIndexName),
+							errhint("The cluster may have exceeded its flood-stage disk watermark.  Free some disk space "
+									"and then clear the block")));
+	}
+
+	freeStringInfo(response);
+	freeStringInfo(request);
+}
+
 static inline void bulk_prologue(ElasticsearchBulkContext *context, bool is_final) {
 	if (rest_multi_perform(context->rest))
 		rest_multi_partial_cleanup(context->rest, false, true);
 
+	if (context->rest->payload_too_large) {
+		/* Elasticsearch said a batch was too large, so make the rest of them smaller */
+		context->rest->payload_too_large = false;
+		context->batchSize = Max(context->batchSize / 2, 1);
+
+		elog(ZDB_LOG_LEVEL, "[zombodb] _bulk request for %s was too large, reducing batch size to %d bytes",
+			 context->pgIndexName, context->batchSize);
+	}
+
 	if (!is_final)
 		remember_curr_xid(context);
 
-	if (context->current->buff->len >= context->batchSize || context->nrows == MAX_DOCS_PER_REQUEST || is_final) {
-		StringInfo request = makeStringInfo();
+	if (context->current->buff->len >= context->batchSize || context->nrows == MAX_DOCS_PER_REQUEST || is_final ||
+		ElasticsearchBulkBatchIsTooOld(context)) {
+		StringInfo             request  = makeStringInfo();
+		int                    inflight = context->bulkConcurrency - context->rest->available;
+		ElasticsearchBulkStats delta;
 
 		if (!is_final) {
 			elog(ZDB_LOG_LEVEL,
@@ -411,23 +1190,47 @@ static inline void bulk_prologue(ElasticsearchBulkContext *context, bool is_fina
 				 context->pgIndexName,
 				 context->current->buff->len,
 				 context->nrows,
-				 context->bulkConcurrency - context->rest->available,
+				 inflight,
 				 context->bulkConcurrency);
 		}
 
-		appendStringInfo(request, "%s%s/%s/_bulk?filter_path=%s", context->url, context->esIndexName, context->typeName,
-						 ES_BULK_RESPONSE_FILTER);
+		/*
+		 * a statement's writes find out about a block from the _bulk response's cluster_block_exception
+		 * soon enough.  A build can run long enough that a block set partway through is worth noticing
+		 * ahead of its remaining batches
+		 */
+		if (context->isBuild && context->nrequests > 0 && context->nrequests % WRITE_BLOCK_CHECK_INTERVAL == 0)
+			check_write_blocks(context);
+
+		make_bulk_endpoint(context, request);
 		if (context->waitForActiveShards)
 			appendStringInfo(request, "&wait_for_active_shards=all");
 
 		if (is_final && context->shouldRefresh && context->nrequests == 0)
 			appendStringInfo(request, "&refresh=true");
 
-		rest_multi_call(context->rest, "POST", request, context->current, context->compressionLevel);
+		if (is_final && context->nrequests == 0 && context->ntotal == 1) {
+			/*
+			 * there's only the one document, so just send it ourselves rather than
+			 * going through curl's multi interface
+			 */
+			rest_multi_call_sync(context->rest, request, context->current, context->compressionLevel,
+								 context->ignoreVersionConflicts);
+		} else {
+			rest_multi_call(context->rest, "POST", request, context->current, context->compressionLevel);
+		}
 		freeStringInfo(request);
+		record_dead_letters(context);
+
+		memset(&delta, 0, sizeof(ElasticsearchBulkStats));
+		delta.requests   = 1;
+		delta.documents  = context->nrows;
+		delta.queueDepth = inflight;
+		record_bulk_stats(context, &delta);
 
 		context->nrows = 0;
 		context->nrequests++;
+		publish_active_build(context);
 
 		if (!is_final) {
 			context->current = checkout_batch_pool(context);
@@ -435,16 +1238,59 @@ static inline void bulk_prologue(ElasticsearchBulkContext *context, bool is_fina
 	}
 }
 
+/*
+ * adds the "routing" property to a _bulk action line, if we have one
+ */
+static inline void append_routing(StringInfo buff, const char *routing) {
+	if (routing != NULL) {
+		appendStringInfoString(buff, ",\"routing\":");
+		escape_json(buff, routing);
+	}
+}
+
 static inline void bulk_epilogue(ElasticsearchBulkContext *context) {
+	if (context->nrows == 0 && zdb_batch_max_age_guc > 0)
+		context->batchStarted = GetCurrentTimestamp();
+
 	context->nrows++;
 	context->ntotal++;
 }
 
-void ElasticsearchBulkInsertRow(ElasticsearchBulkContext *context, ItemPointerData *ctid, StringInfo json,
+/*
+ * A document larger than the index's max_document_size is never sent to Elasticsearch.  With on_error='skip'
+ * it becomes a dead letter, otherwise it's an error
+ */
+static void reject_oversized_document(ElasticsearchBulkContext *context, ItemPointerData *ctid, int len) {
+	char          *id = ctid != NULL ? psprintf("%lu", ItemPointerToUint64(ctid)) : NULL;
+	DeadLetter    *letter;
+	MemoryContext oldContext;
+
+	if (!context->skipErrors)
+		ereport(ERROR,
+				(errcode(ERRCODE_PROGRAM_LIMIT_EXCEEDED),
+						errmsg("document %s is %d bytes, larger than the max_document_size of %d for index '%s'",
+							   id ? id : "(unknown)", len, context->maxDocumentSize, context->pgIndexName),
+						errhint("Use WITH (on_error='skip') to skip such documents instead")));
+
+	oldContext = MemoryContextSwitchTo(TopTransactionContext);
+	letter         = palloc(sizeof(DeadLetter));
+	letter->id     = id ? pstrdup(id) : NULL;
+	letter->reason = psprintf("document_too_large: %d bytes is larger than the max_document_size of %d", len,
+							  context->maxDocumentSize);
+	context->rest->dead_letters = lappend(context->rest->dead_letters, letter);
+	MemoryContextSwitchTo(oldContext);
+}
+
+void ElasticsearchBulkInsertRow(ElasticsearchBulkContext *context, ItemPointerData *ctid, char *routing, StringInfo json,
 								CommandId cmin, CommandId cmax, uint64 xmin, uint64 xmax) {
 	int  len;
 	char *as_string;
 
+	if (context->maxDocumentSize > 0 && json->len > context->maxDocumentSize) {
+		reject_oversized_document(context, ctid, json->len);
+		return;
+	}
+
 	bulk_prologue(context, false);
 
 	as_string = json->data;
@@ -467,9 +1313,12 @@ void ElasticsearchBulkInsertRow(ElasticsearchBulkContext *context, ItemPointerDa
 	 * we'll never use the _id for ourselves, so we don't care what it is
 	 */
 	if (ctid != NULL) {
-		appendStringInfo(context->current->buff, "{\"index\":{\"_id\":\"%lu\"}}\n", ItemPointerToUint64(ctid));
+		appendStringInfo(context->current->buff, "{\"%s\":{\"_id\":\"%lu\"", context->indexAction,
+						 ItemPointerToUint64(ctid));
+		append_routing(context->current->buff, routing);
+		appendStringInfo(context->current->buff, "}}\n");
 	} else {
-		appendStringInfo(context->current->buff, "{\"index\":{}}\n");
+		appendStringInfo(context->current->buff, "{\"%s\":{}}\n", context->indexAction);
 	}
 
 	/* the second line is the json form of the document... */
@@ -496,29 +1345,31 @@ void ElasticsearchBulkInsertRow(ElasticsearchBulkContext *context, ItemPointerDa
 	bulk_epilogue(context);
 }
 
-void ElasticsearchBulkUpdateTuple(ElasticsearchBulkContext *context, ItemPointer ctid, char *llapi_id, CommandId cmax, uint64 xmax) {
+void ElasticsearchBulkUpdateTuple(ElasticsearchBulkContext *context, ItemPointer ctid, char *llapi_id, char *routing, CommandId cmax, uint64 xmax) {
 	bulk_prologue(context, false);
 
 	if (ctid != NULL) {
-		appendStringInfo(context->current->buff, "{\"update\":{\"_id\":\"%lu\",\"_retry_on_conflict\":1}}\n",
+		appendStringInfo(context->current->buff, "{\"update\":{\"_id\":\"%lu\",\"_retry_on_conflict\":1",
 						 ItemPointerToUint64(ctid));
 	} else {
-		appendStringInfo(context->current->buff, "{\"update\":{\"_id\":\"%s\",\"_retry_on_conflict\":1}}\n", llapi_id);
+		appendStringInfo(context->current->buff, "{\"update\":{\"_id\":\"%s\",\"_retry_on_conflict\":1", llapi_id);
 	}
+	append_routing(context->current->buff, routing);
+	appendStringInfo(context->current->buff, "}}\n");
 	appendStringInfo(context->current->buff,
-					 "{\"script\":{\"source\":\""
-					 "ctx._source.zdb_cmax=params.CMAX;"
-					 "ctx._source.zdb_xmax=params.XMAX;\",\"lang\":\"painless\",\"params\":{\"CMAX\":%u,\"XMAX\":%lu}}}\n",
-					 cmax, xmax);
+					 "{\"script\":{\"source\":%s,\"lang\":\"painless\",\"params\":{\"CMAX\":%u,\"XMAX\":%lu%s}}}\n",
+					 context->updateScript, cmax, xmax, context->updateScriptParams);
 
 	context->nupdate++;
 	bulk_epilogue(context);
 }
 
-void ElasticsearchBulkVacuumXmax(ElasticsearchBulkContext *context, char *_id, uint64 expected_xmax) {
+void ElasticsearchBulkVacuumXmax(ElasticsearchBulkContext *context, char *_id, const char *routing, uint64 expected_xmax) {
 	bulk_prologue(context, false);
 
-	appendStringInfo(context->current->buff, "{\"update\":{\"_id\":\"%s\",\"_retry_on_conflict\":0}}\n", _id);
+	appendStringInfo(context->current->buff, "{\"update\":{\"_id\":\"%s\",\"_retry_on_conflict\":0", _id);
+	append_routing(context->current->buff, routing);
+	appendStringInfo(context->current->buff, "}}\n");
 	appendStringInfo(context->current->buff,
 					 "{\"script\":{\"source\":\""
 					 "if (ctx._source.zdb_xmax != params.EXPECTED_XMAX) {"
@@ -532,13 +1383,15 @@ void ElasticsearchBulkVacuumXmax(ElasticsearchBulkContext *context, char *_id, u
 	bulk_epilogue(context);
 }
 
-void ElasticsearchBulkDeleteRowByXmin(ElasticsearchBulkContext *context, char *_id, uint64 xmin) {
+void ElasticsearchBulkDeleteRowByXmin(ElasticsearchBulkContext *context, char *_id, const char *routing, uint64 xmin) {
 	/* important to tag this before we do the work in bulk_prologue() */
 	context->waitForActiveShards = true;
 
 	bulk_prologue(context, false);
 
-	appendStringInfo(context->current->buff, "{\"update\":{\"_id\":\"%s\"}}\n", _id);
+	appendStringInfo(context->current->buff, "{\"update\":{\"_id\":\"%s\"", _id);
+	append_routing(context->current->buff, routing);
+	appendStringInfo(context->current->buff, "}}\n");
 	appendStringInfo(context->current->buff,
 					 "{\"script\":{\"source\":\""
 					 "if (ctx._source.zdb_xmin == params.EXPECTED_XMIN) {"
@@ -552,13 +1405,15 @@ void ElasticsearchBulkDeleteRowByXmin(ElasticsearchBulkContext *context, char *_
 	bulk_epilogue(context);
 }
 
-void ElasticsearchBulkDeleteRowByXmax(ElasticsearchBulkContext *context, char *_id, uint64 xmax) {
+void ElasticsearchBulkDeleteRowByXmax(ElasticsearchBulkContext *context, char *_id, const char *routing, uint64 xmax) {
 	/* important to tag this before we do the work in bulk_prologue() */
 	context->waitForActiveShards = true;
 
 	bulk_prologue(context, false);
 
-	appendStringInfo(context->current->buff, "{\"update\":{\"_id\":\"%s\"}}\n", _id);
+	appendStringInfo(context->current->buff, "{\"update\":{\"_id\":\"%s\"", _id);
+	append_routing(context->current->buff, routing);
+	appendStringInfo(context->current->buff, "}}\n");
 	appendStringInfo(context->current->buff,
 					 "{\"script\":{\"source\":\""
 					 "if (ctx._source.zdb_xmax == params.EXPECTED_XMAX) {"
@@ -589,8 +1444,9 @@ static void mark_transaction_committed(ElasticsearchBulkContext *context, Transa
 }
 
 void ElasticsearchFinishBulkProcess(ElasticsearchBulkContext *context, bool is_commit) {
-	StringInfo request  = makeStringInfo();
-	bool       did_xids = false;
+	StringInfo             request  = makeStringInfo();
+	bool                   did_xids = false;
+	ElasticsearchBulkStats delta;
 
 	if (is_commit) {
 		if (context->rest->available == context->rest->nhandles) {
@@ -641,12 +1497,33 @@ void ElasticsearchFinishBulkProcess(ElasticsearchBulkContext *context, bool is_c
 		rest_multi_wait_for_all_done(context->rest);
 	}
 
+	/* collect the responses of everything that's finished, then finalize */
+	rest_multi_partial_cleanup(context->rest, false, false);
+	record_dead_letters(context);
+	if (context->nrequests > 0) {
+		/* only indexes we've actually sent something for have stats */
+		memset(&delta, 0, sizeof(ElasticsearchBulkStats));
+		delta.versionConflicts = context->rest->nconflicts;
+		delta.latencies        = context->rest->latencies;
+		record_bulk_stats(context, &delta);
+	}
+
+	if (context->nrequests > 1) {
+		elog(ZDB_LOG_LEVEL,
+			 "[zombodb] _bulk request latencies for %s: p50=%ldms, p95=%ldms, p99=%ldms",
+			 context->pgIndexName,
+			 latency_histogram_percentile(&context->rest->latencies, 50),
+			 latency_histogram_percentile(&context->rest->latencies, 95),
+			 latency_histogram_percentile(&context->rest->latencies, 99));
+	}
+
 	/* after this call, context->rest is no longer usable */
 	rest_multi_partial_cleanup(context->rest, true, false);
 
 	if (!is_commit) {
 		/* reset the context->rest struct so that this bulk process can still be used again */
-		context->rest       = rest_multi_init(context->bulkConcurrency, context->ignoreVersionConflicts);
+		context->rest       = rest_multi_init(context->bulkConcurrency, context->ignoreVersionConflicts,
+											  context->skipErrors);
 		context->rest->pool = context->pool;
 	}
 
@@ -663,20 +1540,22 @@ void ElasticsearchFinishBulkProcess(ElasticsearchBulkContext *context, bool is_c
 			mark_transaction_committed(context, (TransactionId) lfirst_int(lc));
 		}
 
-		appendStringInfo(endpoint, "%s%s/%s/_bulk?filter_path=%s", context->url, context->esIndexName,
-						 context->typeName, ES_BULK_RESPONSE_FILTER);
+		make_bulk_endpoint(context, endpoint);
 		rest_call("POST", endpoint, context->current->buff, context->compressionLevel);
 	}
 
 	if (context->shouldRefresh && context->nrequests > 1) {
 		/* we did more than 1 request, so force a full refresh across the entire index */
 		resetStringInfo(request);
-		appendStringInfo(request, "%s%s/_refresh", context->url, context->esIndexName);
+		appendStringInfo(request, "%s%s/_refresh", rest_choose_url(context->url), context->esIndexName);
 		rest_call("POST", request, NULL, context->compressionLevel);
 	}
 
 	freeStringInfo(request);
 
+	if (context->isBuild)
+		ElasticsearchForgetActiveBuilds();
+
 	if (is_commit) {
 		pfree(context->esIndexName);
 		pfree(context->pgIndexName);
@@ -693,7 +1572,7 @@ uint64 ElasticsearchCountAllDocs(Relation indexRel) {
 	appendStringInfo(postData, "{\"query\":{\"match_all\":{}}}");
 	appendStringInfo(request,
 					 "%s%s/%s/_count?filter_path=count",
-					 ZDBIndexOptionsGetUrl(indexRel), ZDBIndexOptionsGetIndexName(indexRel),
+					 ZDBIndexOptionsGetSearchUrl(indexRel), ZDBIndexOptionsGetIndexName(indexRel),
 					 ZDBIndexOptionsGetTypeName(indexRel));
 	response = rest_call("GET", request, postData, ZDBIndexOptionsGetCompressionLevel(indexRel));
 	count    = DirectFunctionCall2(json_object_field_text, CStringGetTextDatum(response->data),
@@ -703,6 +1582,45 @@ uint64 ElasticsearchCountAllDocs(Relation indexRel) {
 	return DatumGetUInt64(DirectFunctionCall1(int8in, PointerGetDatum(TextDatumGetCString(count))));
 }
 
+/*
+ * How many rows are in the index, after refreshing it so that everything we've sent is counted.
+ * The zdb_aborted_xids document isn't a row, so it's not included
+ */
+uint64 ElasticsearchCountIndexedRows(Relation indexRel) {
+	StringInfo request  = makeStringInfo();
+	StringInfo postData = makeStringInfo();
+	StringInfo response;
+	void       *json;
+	uint64     count;
+
+	appendStringInfo(request, "%s%s/_refresh", ZDBIndexOptionsGetUrl(indexRel), ZDBIndexOptionsGetIndexName(indexRel));
+	freeStringInfo(rest_call("POST", request, NULL, ZDBIndexOptionsGetCompressionLevel(indexRel)));
+
+	resetStringInfo(request);
+	appendStringInfo(postData, "{\"query\":{\"bool\":{\"must_not\":{\"ids\":{\"values\":[\"zdb_aborted_xids\"]}}}}}");
+	appendStringInfo(request, "%s%s/_count?filter_path=count", ZDBIndexOptionsGetUrl(indexRel),
+					 ZDBIndexOptionsGetIndexName(indexRel));
+	response = rest_call("POST", request, postData, ZDBIndexOptionsGetCompressionLevel(indexRel));
+	json     = parse_json_object(response, CurrentMemoryContext);
+	count    = get_json_object_uint64(json, "count", false);
+
+	pfree(json);
+	freeStringInfo(response);
+	freeStringInfo(postData);
+	freeStringInfo(request);
+
+	return count;
+}
+
+/*
+ * How many documents has this backend skipped, via on_error='skip', for the specified index?
+ */
+uint64 ElasticsearchBulkDeadLetters(Oid indexRelid) {
+	ElasticsearchBulkStats *stats = hash_search(ElasticsearchGetBulkStats(), &indexRelid, HASH_FIND, NULL);
+
+	return stats == NULL ? 0 : stats->deadLetters;
+}
+
 uint64 ElasticsearchEstimateSelectivity(Relation indexRel, ZDBQueryType *query) {
 	StringInfo request  = makeStringInfo();
 	StringInfo postData = makeStringInfo();
@@ -712,7 +1630,7 @@ uint64 ElasticsearchEstimateSelectivity(Relation indexRel, ZDBQueryType *query)
 	appendStringInfo(postData, "{\"query\":%s}", convert_to_query_dsl(indexRel, query, false));
 	appendStringInfo(request,
 					 "%s%s/%s/_count?filter_path=count",
-					 ZDBIndexOptionsGetUrl(indexRel), ZDBIndexOptionsGetIndexName(indexRel),
+					 ZDBIndexOptionsGetSearchUrl(indexRel), ZDBIndexOptionsGetIndexName(indexRel),
 					 ZDBIndexOptionsGetTypeName(indexRel));
 	response = rest_call("GET", request, postData, ZDBIndexOptionsGetCompressionLevel(indexRel));
 	count    = DirectFunctionCall2(json_object_field_text, CStringGetTextDatum(response->data),
@@ -787,13 +1705,14 @@ ElasticsearchScrollContext *ElasticsearchOpenScroll(Relation indexRel, ZDBQueryT
 
 	appendStringInfo(request,
 					 "%s%s/%s/_search?search_type=%s&_source=false&size=%lu&scroll=10m&filter_path=%s&stored_fields=%s&docvalue_fields=%s",
-					 ZDBIndexOptionsGetUrl(indexRel), ZDBIndexOptionsGetIndexName(indexRel),
+					 ZDBIndexOptionsGetSearchUrl(indexRel), ZDBIndexOptionsGetIndexName(indexRel),
 					 ZDBIndexOptionsGetTypeName(indexRel),
 					 needScore ? "dfs_query_then_fetch" : "query_then_fetch",
-					 limit == 0 ? MAX_DOCS_PER_REQUEST : Min(MAX_DOCS_PER_REQUEST, limit + offset),
+					 limit == 0 ? zdb_scroll_size_guc : Min(zdb_scroll_size_guc, limit + offset),
 					 needScore ? ES_SEARCH_RESPONSE_FILTER : ES_SEARCH_RESPONSE_FILTER_NO_SCORE,
 					 highlights ? "type" : use_id ? "_id" : "_none_",
 					 docvalueFields->data);
+	finish_search_request(request, indexRel);
 
 elog(LOG, "%s", postData->data);
 	response = rest_call("POST", request, postData, ZDBIndexOptionsGetCompressionLevel(indexRel));
@@ -811,7 +1730,7 @@ elog(LOG, "%s", postData->data);
 
 	hitsObject = get_json_object_object(jsonResponse, "hits", false);
 
-	context->url              = ZDBIndexOptionsGetUrl(indexRel);
+	context->url              = ZDBIndexOptionsGetSearchUrl(indexRel);
 	context->compressionLevel = ZDBIndexOptionsGetCompressionLevel(indexRel);
 
 	context->usingId       = use_id;
@@ -927,6 +1846,7 @@ start_over:
 
 	context->hitEntry = get_json_array_element_object(context->hits, context->currpos, context->jsonMemoryContext);
 	context->fields   = get_json_object_object(context->hitEntry, "fields", true);
+	context->routing  = get_json_object_string(context->hitEntry, "_routing", true);
 
 	if (context->usingId) {
 		es_id = (char *) get_json_object_string(context->hitEntry, "_id", false);
@@ -1044,6 +1964,45 @@ void ElasticSearchForceMerge(Relation indexRel) {
 	freeStringInfo(request);
 }
 
+bool ElasticsearchRefreshIndex(Relation indexRel) {
+	StringInfo request = makeStringInfo();
+	StringInfo response;
+	void       *json;
+	void       *shards;
+	uint64     failed  = 0;
+
+	/* make sure anything we've got queued up for this index is sent before we refresh */
+	finish_inserts(false);
+
+	appendStringInfo(request, "%s%s/_refresh", ZDBIndexOptionsGetUrl(indexRel), ZDBIndexOptionsGetIndexName(indexRel));
+	response = rest_call("POST", request, NULL, ZDBIndexOptionsGetCompressionLevel(indexRel));
+	json     = parse_json_object(response, CurrentMemoryContext);
+	shards   = get_json_object_object(json, "_shards", true);
+	if (shards != NULL)
+		failed = get_json_object_uint64(shards, "failed", true);
+
+	pfree(json);
+	freeStringInfo(response);
+	freeStringInfo(request);
+
+	return failed == 0;
+}
+
+/*
+ * Commit what's in the index's translog to Lucene segments, waiting for any flush that's
+ * already running rather than skipping it
+ */
+void ElasticsearchFlushIndex(Relation indexRel) {
+	StringInfo request = makeStringInfo();
+
+	appendStringInfo(request, "%s%s/_flush?wait_if_ongoing=true", ZDBIndexOptionsGetUrl(indexRel),
+					 ZDBIndexOptionsGetIndexName(indexRel));
+	freeStringInfo(rest_call("POST", request, NULL, ZDBIndexOptionsGetCompressionLevel(indexRel)));
+	freeStringInfo(request);
+
+	elog(ZDB_LOG_LEVEL, "[zombodb] flushed %s", RelationGetRelationName(indexRel));
+}
+
 char *ElasticsearchProfileQuery(Relation indexRel, ZDBQueryType *query) {
 	StringInfo request  = makeStringInfo();
 	StringInfo postData = makeStringInfo();
@@ -1051,8 +2010,9 @@ char *ElasticsearchProfileQuery(Relation indexRel, ZDBQueryType *query) {
 
 	appendStringInfo(postData, "{\"profile\":true, \"query\":%s}", convert_to_query_dsl(indexRel, query, false));
 
-	appendStringInfo(request, "%s%s/_search?size=0&filter_path=profile&pretty", ZDBIndexOptionsGetUrl(indexRel),
+	appendStringInfo(request, "%s%s/_search?size=0&filter_path=profile&pretty", ZDBIndexOptionsGetSearchUrl(indexRel),
 					 ZDBIndexOptionsGetIndexName(indexRel));
+	finish_search_request(request, indexRel);
 	response = rest_call("POST", request, postData, ZDBIndexOptionsGetCompressionLevel(indexRel));
 
 	freeStringInfo(postData);
@@ -1061,6 +2021,112 @@ char *ElasticsearchProfileQuery(Relation indexRel, ZDBQueryType *query) {
 	return response->data;
 }
 
+/*
+ * Add a 'routing' parameter, introduced with 'sep', for the document of the row at 'ctid', if the
+ * index routes its documents by a column's value.  Otherwise Elasticsearch would look for the
+ * document on the shard its _id routes to
+ */
+static void append_routing_parameter(StringInfo request, Relation indexRel, ItemPointer ctid, char sep) {
+	char *routing = lookup_routing_for_ctid(indexRel, ctid);
+
+	if (routing != NULL)
+		appendStringInfo(request, "%crouting=%s", sep, rest_url_encode(routing));
+}
+
+char *ElasticsearchTermVectors(Relation indexRel, ItemPointer ctid, char *field) {
+	StringInfo request = makeStringInfo();
+	StringInfo response;
+	void       *json, *termVectors;
+	char       *result = NULL;
+
+	/* make sure the document reflects any changes we haven't yet sent */
+	finish_inserts(false);
+
+	appendStringInfo(request, "%s%s/%s/%lu/_termvectors?fields=%s&term_statistics=true&field_statistics=true&filter_path=found,term_vectors",
+					 ZDBIndexOptionsGetSearchUrl(indexRel), ZDBIndexOptionsGetIndexName(indexRel),
+					 ZDBIndexOptionsGetTypeName(indexRel), ItemPointerToUint64(ctid), rest_url_encode(field));
+	append_routing_parameter(request, indexRel, ctid, '&');
+	response = rest_call("GET", request, NULL, ZDBIndexOptionsGetCompressionLevel(indexRel));
+	json     = parse_json_object(response, CurrentMemoryContext);
+
+	/* a document without any terms for the field doesn't have a "term_vectors" property at all */
+	if (get_json_object_bool(json, "found", false)) {
+		termVectors = get_json_object_object(json, "term_vectors", true);
+		if (termVectors != NULL && (termVectors = get_json_object_object(termVectors, field, true)) != NULL)
+			result = pstrdup(write_json(termVectors));
+	}
+
+	pfree(json);
+	freeStringInfo(response);
+	freeStringInfo(request);
+
+	return result;
+}
+
+/*
+ * Elasticsearch's explanation of how the row with the specified ctid scores against the query.
+ * The explanation says "matched":false if the row doesn't match, or isn't in the index at all
+ */
+char *ElasticsearchExplain(Relation indexRel, ZDBQueryType *query, ItemPointer ctid) {
+	StringInfo request  = makeStringInfo();
+	StringInfo postData = makeStringInfo();
+	StringInfo response;
+	char       *explanation;
+
+	/* make sure the document reflects any changes we haven't yet sent */
+	finish_inserts(false);
+
+	appendStringInfo(postData, "{\"query\":%s}", convert_to_query_dsl(indexRel, query, false));
+
+	appendStringInfo(request, "%s%s/%s/%lu/_explain", ZDBIndexOptionsGetSearchUrl(indexRel),
+					 ZDBIndexOptionsGetIndexName(indexRel), ZDBIndexOptionsGetTypeName(indexRel),
+					 ItemPointerToUint64(ctid));
+	append_routing_parameter(request, indexRel, ctid, '?');
+	response    = rest_call("POST", request, postData, ZDBIndexOptionsGetCompressionLevel(indexRel));
+	explanation = pstrdup(response->data);
+
+	freeStringInfo(response);
+	freeStringInfo(postData);
+	freeStringInfo(request);
+
+	return explanation;
+}
+
+/*
+ * The _source Elasticsearch has for the row with this ctid, without ZomboDB's own zdb_* fields,
+ * or NULL if there's no such document
+ */
+char *ElasticsearchGetDocument(Relation indexRel, ItemPointer ctid) {
+	StringInfo request = makeStringInfo();
+	StringInfo response;
+	int64      response_code;
+	char       *source;
+
+	/* make sure the document reflects any changes we haven't yet sent */
+	finish_inserts(false);
+
+	appendStringInfo(request, "%s%s/%s/%lu/_source?_source_exclude=zdb_*", ZDBIndexOptionsGetSearchUrl(indexRel),
+					 ZDBIndexOptionsGetIndexName(indexRel), ZDBIndexOptionsGetTypeName(indexRel),
+					 ItemPointerToUint64(ctid));
+	append_routing_parameter(request, indexRel, ctid, '&');
+	response = rest_call_unchecked("GET", request, NULL, ZDBIndexOptionsGetCompressionLevel(indexRel),
+								   &response_code);
+	if (response_code == 404) {
+		source = NULL;
+	} else if (response_code != 200) {
+		ereport(ERROR,
+				(errcode(ERRCODE_IO_ERROR),
+						errmsg("%s", response->data)));
+	} else {
+		source = pstrdup(response->data);
+	}
+
+	freeStringInfo(response);
+	freeStringInfo(request);
+
+	return source;
+}
+
 uint64 ElasticsearchCount(Relation indexRel, ZDBQueryType *query) {
 	StringInfo request  = makeStringInfo();
 	StringInfo postData = makeStringInfo();
@@ -1074,7 +2140,7 @@ uint64 ElasticsearchCount(Relation indexRel, ZDBQueryType *query) {
 
 	appendStringInfo(postData, "{\"query\":%s}", convert_to_query_dsl(indexRel, query, true));
 
-	appendStringInfo(request, "%s%s/_count?filter_path=count", ZDBIndexOptionsGetUrl(indexRel),
+	appendStringInfo(request, "%s%s/_count?filter_path=count", ZDBIndexOptionsGetSearchUrl(indexRel),
 					 ZDBIndexOptionsGetAlias(indexRel));
 	response = rest_call("POST", request, postData, ZDBIndexOptionsGetCompressionLevel(indexRel));
 	json     = parse_json_object(response, CurrentMemoryContext);
@@ -1311,8 +2377,9 @@ static char *makeAggRequest(Relation indexRel, ZDBQueryType *query, char *agg, c
 	}
 	appendStringInfoCharMacro(postData, '}');
 
-	appendStringInfo(request, "%s%s/_search?size=0", ZDBIndexOptionsGetUrl(indexRel),
+	appendStringInfo(request, "%s%s/_search?size=0", ZDBIndexOptionsGetSearchUrl(indexRel),
 					 ZDBIndexOptionsGetAlias(indexRel));
+	finish_search_request(request, indexRel);
 	response = rest_call("POST", request, postData, ZDBIndexOptionsGetCompressionLevel(indexRel));
 
 	freeStringInfo(postData);
@@ -21,17 +21,23 @@
 #include "json/json_support.h"
 #include "json/type_conversion.h"
 #include "rest/curl_support.h"
+#include "datatype/timestamp.h"
+#include "utils/hsearch.h"
 #include "utils/jsonb.h"
 
 /* this needs to match curl_support.h:MAX_CURL_HANDLES */
 #define MAX_BULK_CONCURRENCY 1024
 
+/* an ES limit introduced around Elasticsearch v5 */
+#define MAX_DOCS_PER_REQUEST 10000
+
 typedef struct ElasticsearchBulkContext {
-	char           *url;
+	char           *url;    /* all of the index's urls.  See rest_choose_url() */
 	char           *pgIndexName;
 	char           *esIndexName;
 	char           *typeName;
 	int            batchSize;
+	int            maxDocumentSize; /* in bytes, zero for no limit */
 	int            bulkConcurrency;
 	int            compressionLevel;
 	bool           waitForActiveShards;
@@ -39,12 +45,24 @@ typedef struct ElasticsearchBulkContext {
 	bool           containsJsonIsSet;
 	TupleDesc      tupdesc;
 	JsonConversion **jsonConversions;
+	FmgrInfo       *transformFunction; /* the index's 'transform_function', if it has one */
 	bool           shouldRefresh;
+	bool           waitForRefresh; /* refresh_interval='wait_for' */
 	bool           ignoreVersionConflicts;
+	bool           skipErrors;
+	bool           isBuild;     /* for CREATE INDEX/REINDEX, rather than a statement's writes */
+	Oid            indexRelid;
+	char           *routing;     /* name of the column providing routing values, if any */
+	char           *indexAction; /* the _bulk action for new documents, "index" or "create" */
+	char           *bulkPrimaryTimeout; /* the _bulk "timeout" parameter, if any */
+	char           *activeShardCount;   /* the index's "wait_for_active_shards" option, if any */
+	char           *updateScript;       /* json-quoted painless source for xmax/cmax updates */
+	char           *updateScriptParams; /* extra params for updateScript, with a leading comma, or "" */
 	MultiRestState *rest;
 	PostDataEntry  *current;
 	int            nrequests;
 	int            nrows;
+	TimestampTz    batchStarted; /* when the first row of the current batch was added */
 	int            ntotal;
 	int            nindex;
 	int            nupdate;
@@ -56,12 +74,36 @@ typedef struct ElasticsearchBulkContext {
 	List           *usedXids;    /* should be allocated in TopTransactionContext */
 } ElasticsearchBulkContext;
 
+/* what this session, or every session, has sent to Elasticsearch, per index.  See zdb.bulk_stats() */
+typedef struct ElasticsearchBulkStats {
+	Oid    indexRelid;        /* hash key */
+	uint64 requests;          /* _bulk requests sent */
+	uint64 documents;         /* documents (index, update, and delete actions) sent */
+	uint64 versionConflicts;  /* version conflicts we ignored */
+	uint64 deadLetters;       /* documents Elasticsearch rejected that we skipped, because of on_error='skip' */
+	uint64 queueDepth;        /* sum of how many _bulk requests were already in flight as each was sent */
+	LatencyHistogram latencies; /* how long the _bulk requests took */
+} ElasticsearchBulkStats;
+
+/* a CREATE INDEX or REINDEX in progress, as every session sees it.  See zdb.active_builds */
+typedef struct ElasticsearchActiveBuild {
+	int         pid;              /* the building backend, or 0 when the slot is free */
+	Oid         databaseOid;
+	Oid         indexRelid;
+	TimestampTz started;
+	uint64      rows;             /* rows queued for Elasticsearch so far */
+	uint64      requests;         /* _bulk requests sent so far */
+	int         inflight;         /* _bulk requests waiting on a response */
+	int         bulkConcurrency;  /* how many _bulk requests can be in flight at once */
+} ElasticsearchActiveBuild;
+
 typedef struct ElasticsearchScrollContext {
 	MemoryContext jsonMemoryContext;      /* where are json objects allocated? */
 	char          *url;
 	int           compressionLevel;
 	bool          usingId;    /* is this scroll using _id instead of zdb_id? */
 	const char    *scrollId;
+	const char    *routing;   /* the _routing of the current hit, if it has one */
 	bool          hasHighlights;
 	bool          needScore;
 	uint64        total;      /* total number of hits across all scroll context's */
@@ -75,6 +117,13 @@ typedef struct ElasticsearchScrollContext {
 	int           nextraFields;
 } ElasticsearchScrollContext;
 
+/* what a CREATE INDEX or REINDEX does when the cluster's nodes run different major versions.  See zdb.check_node_versions */
+typedef enum CheckNodeVersions {
+	CHECK_NODE_VERSIONS_OFF,     /* don't check */
+	CHECK_NODE_VERSIONS_WARNING, /* raise a WARNING and build the index anyways */
+	CHECK_NODE_VERSIONS_ERROR    /* raise an ERROR */
+} CheckNodeVersions;
+
 /* defined in zdbam.c */
 extern int ZDB_LOG_LEVEL;
 
@@ -82,7 +131,11 @@ char *make_alias_name(Relation indexRel, bool force_default);
 
 char *ElasticsearchArbitraryRequest(Relation indexRel, char *method, char *endpoint, StringInfo postData);
 
+void ElasticsearchCheckClusterHealth(Relation indexRel);
+void ElasticsearchCheckNodeVersions(Relation indexRel);
+void ElasticsearchCompareNodeVersions(const char *url, StringInfo response);
 char *ElasticsearchCreateIndex(Relation heapRel, Relation indexRel, TupleDesc tupdesc, char *aliasName);
+bool ElasticsearchIndexExists(Relation indexRel);
 void ElasticsearchDeleteIndex(Relation indexRel);
 void ElasticsearchDeleteIndexDirect(char *index_url);
 void ElasticsearchFinalizeIndexCreation(Relation indexRel);
@@ -90,16 +143,25 @@ void ElasticsearchFinalizeIndexCreation(Relation indexRel);
 void ElasticsearchUpdateSettings(Relation indexRel, char *oldAlias, char *newAlias);
 void ElasticsearchPutMapping(Relation heapRel, Relation indexRel, TupleDesc tupdesc);
 
-ElasticsearchBulkContext *ElasticsearchStartBulkProcess(Relation indexRel, char *indexName, TupleDesc tupdesc, bool ignore_version_conflicts);
-void ElasticsearchBulkInsertRow(ElasticsearchBulkContext *context, ItemPointerData *ctid, StringInfo json,
+void ElasticsearchBulkStatsShmemRequest(void);
+HTAB *ElasticsearchGetBulkStats(void);
+List *ElasticsearchGetSharedBulkStats(void);
+void ElasticsearchForgetBulkStats(Oid indexRelid);
+List *ElasticsearchGetActiveBuilds(void);
+void ElasticsearchForgetActiveBuilds(void);
+ElasticsearchBulkContext *ElasticsearchStartBulkProcess(Relation indexRel, char *indexName, TupleDesc tupdesc, bool ignore_version_conflicts, bool is_build);
+void ElasticsearchBulkInsertRow(ElasticsearchBulkContext *context, ItemPointerData *ctid, char *routing, StringInfo json,
 								CommandId cmin, CommandId cmax, uint64 xmin, uint64 xmax);
-void ElasticsearchBulkUpdateTuple(ElasticsearchBulkContext *context, ItemPointer ctid, char *llapi_id, CommandId cmax, uint64 xmax);
-void ElasticsearchBulkVacuumXmax(ElasticsearchBulkContext *context, char *_id, uint64 expected_xmax);
-void ElasticsearchBulkDeleteRowByXmin(ElasticsearchBulkContext *context, char *_id, uint64 xmin);
-void ElasticsearchBulkDeleteRowByXmax(ElasticsearchBulkContext *context, char *_id, uint64 xmax);
+void ElasticsearchBulkUpdateTuple(ElasticsearchBulkContext *context, ItemPointer ctid, char *llapi_id, char *routing, CommandId cmax, uint64 xmax);
+void ElasticsearchBulkVacuumXmax(ElasticsearchBulkContext *context, char *_id, const char *routing, uint64 expected_xmax);
+void ElasticsearchBulkDeleteRowByXmin(ElasticsearchBulkContext *context, char *_id, const char *routing, uint64 xmin);
+void ElasticsearchBulkDeleteRowByXmax(ElasticsearchBulkContext *context, char *_id, const char *routing, uint64 xmax);
+bool ElasticsearchBulkBatchIsTooOld(ElasticsearchBulkContext *context);
 void ElasticsearchFinishBulkProcess(ElasticsearchBulkContext *context, bool is_commit);
 
 uint64 ElasticsearchCountAllDocs(Relation indexRel);
+uint64 ElasticsearchCountIndexedRows(Relation indexRel);
+uint64 ElasticsearchBulkDeadLetters(Oid indexRelid);
 uint64 ElasticsearchEstimateSelectivity(Relation indexRel, ZDBQueryType *query);
 
 ElasticsearchScrollContext *ElasticsearchOpenScroll(Relation indexRel, ZDBQueryType *userQuery, bool use_id, uint64 limit, List *highlights, char **extraFields, int nextraFields);
@@ -109,8 +171,13 @@ void ElasticsearchCloseScroll(ElasticsearchScrollContext *scrollContext);
 
 void ElasticsearchRemoveAbortedTransactions(Relation indexRel, List/*uint64*/ *xids);
 void ElasticSearchForceMerge(Relation indexRel);
+bool ElasticsearchRefreshIndex(Relation indexRel);
+void ElasticsearchFlushIndex(Relation indexRel);
 
 char *ElasticsearchProfileQuery(Relation indexRel, ZDBQueryType *query);
+char *ElasticsearchTermVectors(Relation indexRel, ItemPointer ctid, char *field);
+char *ElasticsearchExplain(Relation indexRel, ZDBQueryType *query, ItemPointer ctid);
+char *ElasticsearchGetDocument(Relation indexRel, ItemPointer ctid);
 
 bool ElasticsearchIsNestedField(Relation indexRel, const char *field, char **base);
 
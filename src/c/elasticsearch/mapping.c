@@ -15,21 +15,25 @@
  */
 
 #include "mapping.h"
+#include "json/json.h"
+#include "json/json_support.h"
 
 #include "executor/spi.h"
+#include "utils/lsyscache.h"
 #include "utils/typcache.h"
+#include "utils/varlena.h"
 
-static bool lookup_field_mapping(Oid tableRelId, char *fieldname, StringInfo mapping, MemoryContext memcxt) {
-	static Oid   types[2]  = {REGCLASSOID, TEXTOID};
-	Datum datums[2] = {ObjectIdGetDatum(tableRelId), CStringGetTextDatum(fieldname)};
-	char  nulls[2]  = {0, 0};
+static bool lookup_field_mapping(Oid tableRelId, char *fieldname, char *esname, StringInfo mapping, MemoryContext memcxt) {
+	static Oid   types[3]  = {REGCLASSOID, TEXTOID, TEXTOID};
+	Datum datums[3] = {ObjectIdGetDatum(tableRelId), CStringGetTextDatum(fieldname), CStringGetTextDatum(esname)};
+	char  nulls[3]  = {0, 0, 0};
 	int   res;
 	bool  rc        = false;
 
 	SPI_connect();
 	if ((res = SPI_execute_with_args(
-			"select (to_json(field_name) || ':' || definition) from zdb.mappings where table_name = $1::regclass and field_name = $2;",
-			2,
+			"select (to_json($3) || ':' || definition) from zdb.mappings where table_name = $1::regclass and field_name = $2;",
+			3,
 			types,
 			datums,
 			nulls,
@@ -55,6 +59,42 @@ static bool lookup_field_mapping(Oid tableRelId, char *fieldname, StringInfo map
 	return rc;
 }
 
+static bool lookup_field_mapping_option(struct json_value_s *overrides, char *fieldname, char *esname, StringInfo mapping) {
+	struct json_object_element_s *elem;
+
+	if (overrides == NULL)
+		return false;
+
+	for (elem = ((struct json_object_s *) overrides->payload)->start; elem != NULL; elem = elem->next) {
+		if (strcmp(fieldname, elem->name->string) == 0) {
+			appendStringInfo(mapping, ", \"%s\":%s", esname, write_json(elem->value));
+			return true;
+		}
+	}
+
+	return false;
+}
+
+void validate_field_mapping_option(char *str) {
+	struct json_value_s          *json = parse_json_object_from_string(str, CurrentMemoryContext);
+	struct json_object_element_s *elem;
+
+	if (json->type != json_type_object)
+		ereport(ERROR,
+				(errcode(ERRCODE_INVALID_PARAMETER_VALUE),
+						errmsg("'field_mapping' index option must be a json object keyed by column name")));
+
+	for (elem = ((struct json_object_s *) json->payload)->start; elem != NULL; elem = elem->next) {
+		if (elem->value->type != json_type_object)
+			ereport(ERROR,
+					(errcode(ERRCODE_INVALID_PARAMETER_VALUE),
+							errmsg("'field_mapping' definition for column '%s' must be a json object",
+								   elem->name->string)));
+	}
+
+	pfree(json);
+}
+
 static bool lookup_type_mapping(Oid typeOid, int32 typmod, char *fieldname, StringInfo mapping, MemoryContext memcxt) {
 	static Oid   types[1]  = {REGTYPEOID};
 	Datum datums[1] = {ObjectIdGetDatum(typeOid)};
@@ -212,10 +252,181 @@ static List *lookup_es_only_fields(MemoryContext cxt, Oid tableOid) {
 }
 
 
-StringInfo generate_mapping(Relation heapRel, TupleDesc tupdesc) {
-	StringInfo mapping = makeStringInfo();
-	int        i;
-	ListCell   *lc;
+/*
+ * every column named by the 'include_columns' or 'exclude_columns' option must be a column we're indexing
+ */
+static void validate_column_list_option(char *optionName, char *list, TupleDesc tupdesc) {
+	List     *names;
+	ListCell *lc;
+
+	if (list == NULL)
+		return;
+
+	if (!SplitIdentifierString(pstrdup(list), ',', &names))
+		elog(ERROR, "'%s' index option must be a comma-separated list of column names", optionName);
+
+	foreach (lc, names) {
+		char *name  = lfirst(lc);
+		bool found = false;
+		int  i;
+
+		for (i = 0; i < tupdesc->natts; i++) {
+			Form_pg_attribute attr = TupleDescAttr(tupdesc, i);
+
+			if (!attr->attisdropped && strcmp(name, NameStr(attr->attname)) == 0) {
+				found = true;
+				break;
+			}
+		}
+
+		if (!found)
+			ereport(ERROR,
+					(errcode(ERRCODE_UNDEFINED_COLUMN),
+							errmsg("'%s' index option references unknown column '%s'", optionName, name)));
+	}
+}
+
+/*
+ * Append the mapping for a single field of the specified type, based on the type mappings
+ * we have in the database, or failing that, what we can figure out about the type itself
+ */
+static void append_type_mapping(StringInfo mapping, char *fieldname, Oid atttypid, int32 typmod, char *defaultAnalyzer) {
+	Oid            typeOid = get_base_type_oid(atttypid);
+	TypeCacheEntry *cacheEntry;
+	char           *typename;
+
+	if (defaultAnalyzer != NULL && (atttypid == TEXTOID || atttypid == TEXTARRAYOID)) {
+		/* plain text columns use the index's 'default_analyzer' rather than the 'text' type mapping */
+		appendStringInfo(mapping,
+						 ", \"%s\": {\"type\":\"text\",\"copy_to\":\"zdb_all\",\"fielddata\":true,\"analyzer\":\"%s\"}",
+						 fieldname, defaultAnalyzer);
+		return;
+	}
+
+	/* apply type-specific mapping, if we have it in the database */
+	if (lookup_type_mapping(typeOid, typmod, fieldname, mapping, CurrentMemoryContext))
+		return;
+
+	if (get_typtype(typeOid) == TYPTYPE_COMPOSITE) {
+		/*
+		 * it's a composite type (or an array of them), so map it as 'nested' and recurse through
+		 * its attributes so that each object keeps its own boundaries in Elasticsearch
+		 */
+		TupleDesc  compositeDesc = lookup_rowtype_tupdesc(typeOid, -1);
+		StringInfo properties    = makeStringInfo();
+		int        i;
+
+		for (i = 0; i < compositeDesc->natts; i++) {
+			Form_pg_attribute attr = TupleDescAttr(compositeDesc, i);
+
+			if (attr->attisdropped)
+				continue;
+
+			append_type_mapping(properties, NameStr(attr->attname), attr->atttypid, attr->atttypmod, defaultAnalyzer);
+		}
+		ReleaseTupleDesc(compositeDesc);
+
+		/* each property was appended with a leading comma, which we don't want for the first one */
+		appendStringInfo(mapping, ", \"%s\": {\"type\":\"nested\",\"include_in_parent\":true,\"properties\":{%s}}",
+						 fieldname, properties->len > 0 ? properties->data + 1 : "");
+		return;
+	}
+
+	/* figure out what to do based on heuristics regarding DOMAINs that map to analyzer names */
+	appendStringInfo(mapping, ", \"%s\": {", fieldname);
+	cacheEntry = lookup_type_cache(atttypid, 0);
+
+	typename = DatumGetCString(DirectFunctionCall1(regtypeout, Int32GetDatum(atttypid)));
+	if (cacheEntry->typtype == 'd') {
+		/*
+		 * it's a domain type, so we set it to a type of text or keyword
+		 * depending on if its base type is ::text or ::varchar
+		 *
+		 * we also assign an analyzer (for text) or normalizer (for keyword) that is the same name as the domain
+		 * type itself
+		 */
+		Oid  base_type = InvalidOid;
+
+		type_is_domain(typename, &base_type);
+
+		/* strip schema name, if we have one */
+		if (strchr(typename, '.') != 0) {
+			typename = strchr(typename, '.') + 1;
+		}
+
+		switch (base_type) {
+			case VARCHAROID:
+				if (strcmp("keyword", typename) == 0) {
+					/* if the typename is 'keyword', then we don't need to set the normalizer */
+					appendStringInfo(mapping, "\"type\":\"keyword\","
+											  "\"ignore_above\": 10922");
+				} else {
+					/* otherwise, the normalizer is set to the typename */
+					appendStringInfo(mapping, "\"type\":\"keyword\","
+											  "\"ignore_above\": 10922,"
+											  "\"normalizer\":\"%s\"", typename);
+				}
+				break;
+			case TEXTOID:
+				appendStringInfo(mapping, "\"type\":\"text\","
+										  "\"analyzer\":\"%s\"", typename);
+				break;
+			default:
+				ereport(ERROR,
+						(errcode(ERRCODE_INVALID_TEXT_REPRESENTATION),
+								errmsg("Unsupported base domain type for %s: %u", typename, base_type)));
+		}
+	} else {
+		/* it's a type that we don't have built-in knowledge on how to map, so treat it as a 'keyword' */
+		elog(NOTICE, "[zombodb] unrecognized data type '%s', mapping to 'keyword'", typename);
+		appendStringInfo(mapping, "\"type\":\"keyword\","
+								  "\"ignore_above\": 10922,"
+								  "\"normalizer\":\"lowercase\"");
+	}
+
+	appendStringInfo(mapping, "}");
+}
+
+StringInfo generate_mapping(Relation heapRel, Relation indexRel, TupleDesc tupdesc) {
+	StringInfo          mapping       = makeStringInfo();
+	char                *fieldMapping = ZDBIndexOptionsGetFieldMapping(indexRel);
+	struct json_value_s *overrides    = NULL;
+	int                 i;
+	ListCell            *lc;
+
+	if (fieldMapping != NULL) {
+		struct json_object_element_s *elem;
+
+		validate_field_mapping_option(fieldMapping);
+		overrides = parse_json_object_from_string(fieldMapping, CurrentMemoryContext);
+
+		/* every field we've been asked to override must be a column we're indexing */
+		for (elem = ((struct json_object_s *) overrides->payload)->start; elem != NULL; elem = elem->next) {
+			bool found = false;
+
+			for (i = 0; i < tupdesc->natts; i++) {
+				Form_pg_attribute attr = TupleDescAttr(tupdesc, i);
+
+				if (!attr->attisdropped && strcmp(elem->name->string, NameStr(attr->attname)) == 0) {
+					found = true;
+					break;
+				}
+			}
+
+			if (!found)
+				ereport(ERROR,
+						(errcode(ERRCODE_UNDEFINED_COLUMN),
+								errmsg("'field_mapping' index option references unknown column '%s'",
+									   elem->name->string)));
+		}
+	}
+
+	if (ZDBIndexOptionsGetIncludeColumns(indexRel) != NULL && ZDBIndexOptionsGetExcludeColumns(indexRel) != NULL)
+		ereport(ERROR,
+				(errcode(ERRCODE_INVALID_PARAMETER_VALUE),
+						errmsg("only one of the 'include_columns' and 'exclude_columns' index options can be set")));
+	validate_column_list_option("include_columns", ZDBIndexOptionsGetIncludeColumns(indexRel), tupdesc);
+	validate_column_list_option("exclude_columns", ZDBIndexOptionsGetExcludeColumns(indexRel), tupdesc);
 
 	appendStringInfo(mapping, "\"zdb_all\": { \"type\":\"text\", \"analyzer\":\"zdb_all_analyzer\" }");
 	appendStringInfo(mapping, ",\"zdb_ctid\": { \"type\":\"long\" }");
@@ -232,76 +443,24 @@ StringInfo generate_mapping(Relation heapRel, TupleDesc tupdesc) {
 
 	for (i = 0; i < tupdesc->natts; i++) {
         Form_pg_attribute attr = TupleDescAttr(tupdesc, i);
-		Oid               typeOid = get_base_type_oid(attr->atttypid);
-		TypeCacheEntry    *cacheEntry;
-		char *typename;
+		char              *esname;
 
-		/* skip dropped attributes */
-		if (attr->attisdropped)
+		/* skip dropped attributes, and those we've been told not to index */
+		if (attr->attisdropped || !column_is_indexed(indexRel, NameStr(attr->attname)))
 			continue;
 
-		/* apply field-specific mapping, if we have it in the database */
-		if (lookup_field_mapping(RelationGetRelid(heapRel), NameStr(attr->attname), mapping, CurrentMemoryContext))
-			continue;
+		esname = es_field_name(indexRel, NameStr(attr->attname));
 
-		/* apply type-specific mapping, if we have it in the database */
-		if (lookup_type_mapping(typeOid, attr->atttypmod, NameStr(attr->attname), mapping, CurrentMemoryContext))
+		/* apply field-specific mapping from the index's 'field_mapping' option, if it has one */
+		if (lookup_field_mapping_option(overrides, NameStr(attr->attname), esname, mapping))
 			continue;
 
+		/* apply field-specific mapping, if we have it in the database */
+		if (lookup_field_mapping(RelationGetRelid(heapRel), NameStr(attr->attname), esname, mapping, CurrentMemoryContext))
+			continue;
 
-		/* figure out what to do based on heuristics regarding DOMAINs that map to analyzer names */
-		appendStringInfo(mapping, ", \"%s\": {", NameStr(attr->attname));
-		cacheEntry = lookup_type_cache(attr->atttypid, 0);
-
-		typename = DatumGetCString(DirectFunctionCall1(regtypeout, Int32GetDatum(attr->atttypid)));
-		if (cacheEntry->typtype == 'd') {
-			/*
-			 * it's a domain type, so we set it to a type of text or keyword
-			 * depending on if its base type is ::text or ::varchar
-			 *
-			 * we also assign an analyzer (for text) or normalizer (for keyword) that is the same name as the domain
-			 * type itself
-			 */
-			Oid  base_type = InvalidOid;
-
-			type_is_domain(typename, &base_type);
-
-			/* strip schema name, if we have one */
-			if (strchr(typename, '.') != 0) {
-				typename = strchr(typename, '.') + 1;
-			}
-
-			switch (base_type) {
-				case VARCHAROID:
-					if (strcmp("keyword", typename) == 0) {
-						/* if the typename is 'keyword', then we don't need to set the normalizer */
-						appendStringInfo(mapping, "\"type\":\"keyword\","
-												  "\"ignore_above\": 10922");
-					} else {
-						/* otherwise, the normalizer is set to the typename */
-						appendStringInfo(mapping, "\"type\":\"keyword\","
-												  "\"ignore_above\": 10922,"
-												  "\"normalizer\":\"%s\"", typename);
-					}
-					break;
-				case TEXTOID:
-					appendStringInfo(mapping, "\"type\":\"text\","
-											  "\"analyzer\":\"%s\"", typename);
-					break;
-				default:
-					ereport(ERROR,
-							(errcode(ERRCODE_INVALID_TEXT_REPRESENTATION),
-									errmsg("Unsupported base domain type for %s: %u", typename, base_type)));
-			}
-		} else {
-			/* it's a type that we don't have built-in knowledge on how to map, so treat it as a 'keyword' */
-			elog(NOTICE, "[zombodb] unrecognized data type '%s', mapping to 'keyword'", typename);
-			appendStringInfo(mapping, "\"type\":\"keyword\","
-									  "\"ignore_above\": 10922,"
-									  "\"normalizer\":\"lowercase\"");
-		}
-
-		appendStringInfo(mapping, "}");
+		append_type_mapping(mapping, esname, attr->atttypid, attr->atttypmod,
+							ZDBIndexOptionsGetDefaultAnalyzer(indexRel));
 	}
 
 	return mapping;
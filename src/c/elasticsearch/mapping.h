@@ -19,7 +19,8 @@
 
 #include "zombodb.h"
 
-StringInfo generate_mapping(Relation heapRel, TupleDesc tupdesc);
+StringInfo generate_mapping(Relation heapRel, Relation indexRel, TupleDesc tupdesc);
+void validate_field_mapping_option(char *str);
 char *lookup_analysis_thing(MemoryContext cxt, char *thing);
 
 #endif /* __ZDB_MAPPING_H__ */
@@ -19,9 +19,13 @@
 
 #include "access/transam.h"
 #include "access/xact.h"
+#include "catalog/pg_type.h"
+#include "utils/array.h"
+#include "utils/jsonb.h"
 
 PG_FUNCTION_INFO_V1(llapi_direct_insert);
 PG_FUNCTION_INFO_V1(llapi_direct_delete);
+PG_FUNCTION_INFO_V1(llapi_bulk_insert);
 
 Datum llapi_direct_insert(PG_FUNCTION_ARGS) {
 	MemoryContext         oldContext  = MemoryContextSwitchTo(TopTransactionContext);
@@ -42,7 +46,7 @@ Datum llapi_direct_insert(PG_FUNCTION_ARGS) {
 	initStringInfo(&json);
 	appendStringInfoString(&json, TextDatumGetCString(jsonArg));
 	context = checkout_insert_context(indexRel);
-	ElasticsearchBulkInsertRow(context->esContext, NULL, &json, GetCurrentCommandId(true), InvalidCommandId,
+	ElasticsearchBulkInsertRow(context->esContext, NULL, NULL, &json, GetCurrentCommandId(true), InvalidCommandId,
 							   convert_xid(GetCurrentTransactionId()), InvalidTransactionId);
 
 	index_close(indexRel, AccessShareLock);
@@ -51,6 +55,61 @@ Datum llapi_direct_insert(PG_FUNCTION_ARGS) {
 	PG_RETURN_VOID();
 }
 
+/*
+ * Queue every document in the jsonb[] argument through the index's bulk context, the same as
+ * if each had been passed to llapi_direct_insert().  Returns the number of documents queued
+ */
+Datum llapi_bulk_insert(PG_FUNCTION_ARGS) {
+	MemoryContext         oldContext  = MemoryContextSwitchTo(TopTransactionContext);
+	Oid                   indexRelOid = PG_GETARG_OID(0);
+	ArrayType             *documents  = PG_GETARG_ARRAYTYPE_P(1);
+	CommandId             cmin        = GetCurrentCommandId(true);
+	uint64                xmin        = convert_xid(GetCurrentTransactionId());
+	Datum                 *elements;
+	bool                  *nulls;
+	int                   nelements;
+	int                   i;
+	Relation              indexRel;
+	ZDBIndexChangeContext *context;
+
+	indexRel = zdb_open_index(indexRelOid, AccessShareLock);
+
+	if (!ZDBIndexOptionsGetLLAPI(indexRel)) {
+		ereport(ERROR,
+				(errcode(ERRCODE_DATA_EXCEPTION),
+						errmsg("To use ZomboDB's low-level API you must set llapi=true on the index")));
+	}
+
+	deconstruct_array(documents, JSONBOID, -1, false, 'i', &elements, &nulls, &nelements);
+
+	context = checkout_insert_context(indexRel);
+	for (i = 0; i < nelements; i++) {
+		StringInfoData json;
+		Jsonb          *jsonb;
+
+		if (nulls[i])
+			ereport(ERROR,
+					(errcode(ERRCODE_NULL_VALUE_NOT_ALLOWED),
+							errmsg("document %d in zdb.bulk_insert() is NULL", i + 1)));
+
+		jsonb = DatumGetJsonbP(elements[i]);
+		if (!JB_ROOT_IS_OBJECT(jsonb))
+			ereport(ERROR,
+					(errcode(ERRCODE_INVALID_PARAMETER_VALUE),
+							errmsg("document %d in zdb.bulk_insert() is not a json object", i + 1)));
+
+		initStringInfo(&json);
+		JsonbToCString(&json, &jsonb->root, VARSIZE(jsonb));
+		ElasticsearchBulkInsertRow(context->esContext, NULL, NULL, &json, cmin, InvalidCommandId, xmin,
+								   InvalidTransactionId);
+	}
+
+	index_close(indexRel, AccessShareLock);
+
+	MemoryContextSwitchTo(oldContext);
+	PG_RETURN_INT64(nelements);
+}
+
 Datum llapi_direct_delete(PG_FUNCTION_ARGS) {
 	MemoryContext         oldContext  = MemoryContextSwitchTo(TopTransactionContext);
 	Oid                   indexRelOid = PG_GETARG_OID(0);
@@ -67,7 +126,7 @@ Datum llapi_direct_delete(PG_FUNCTION_ARGS) {
 	}
 
 	context = checkout_insert_context(indexRel);
-	ElasticsearchBulkUpdateTuple(context->esContext, NULL, id, GetCurrentCommandId(true),
+	ElasticsearchBulkUpdateTuple(context->esContext, NULL, id, NULL, GetCurrentCommandId(true),
 								 convert_xid(GetCurrentTransactionId()));
 
 	index_close(indexRel, AccessShareLock);
@@ -40,20 +40,66 @@ typedef struct {
 	int   uuidOffset;
 	int   optimizeAfter;
 	bool  llapi;
+	int   fieldMappingOffset;
+	int   onErrorOffset;
+	int   routingOffset;
+	int   indexActionOffset;
+	int   includeColumnsOffset;
+	int   excludeColumnsOffset;
+	int   bulkPrimaryTimeoutOffset;
+	int   hotTuplesOffset;
+	int   cloudIdOffset;
+	bool  allowSharedUuid;
+	int   waitForActiveShardsOffset;
+	int   defaultAnalyzerOffset;
+	int   updateScriptOffset;
+	int   updateScriptParamsOffset;
+	int   maxDocumentSize;
+	bool  flushAfterBuild;
+	int   searchUrlOffset;
+	int   dotReplacementOffset;
+	int   maxConcurrentShardRequests;
+	int   transformFunctionOffset;
+	int   indexSettingsOffset;
+	int   nonfiniteFloatsOffset;
 } ZDBIndexOptions;
 
 #define ZDBIndexOptionsGetUrlMacro(relation) \
     ((relation)->rd_options && ((ZDBIndexOptions *) (relation)->rd_options)->urlValueOffset > 0 ? \
       (char *) ((ZDBIndexOptions *) (relation)->rd_options) + ((ZDBIndexOptions *) (relation)->rd_options)->urlValueOffset : ("default"))
 
+#define ZDBIndexOptionsGetSearchUrls(relation) \
+    ((relation)->rd_options && ((ZDBIndexOptions *) (relation)->rd_options)->searchUrlOffset > 0 ? \
+      (char *) ((ZDBIndexOptions *) (relation)->rd_options) + ((ZDBIndexOptions *) (relation)->rd_options)->searchUrlOffset : (NULL))
+
+#define ZDBIndexOptionsGetCloudId(relation) \
+    ((relation)->rd_options && ((ZDBIndexOptions *) (relation)->rd_options)->cloudIdOffset > 0 ? \
+      (char *) ((ZDBIndexOptions *) (relation)->rd_options) + ((ZDBIndexOptions *) (relation)->rd_options)->cloudIdOffset : (NULL))
+
 /* defined in zdbam.c */
 extern char *zdb_default_elasticsearch_url_guc;
 extern int  zdb_default_row_estimation_guc;
 extern int  zdb_default_replicas_guc;
 
-static inline char *ZDBIndexOptionsGetUrl(Relation rel) {
+/* defined in utils.c */
+extern char *cloud_id_to_url(const char *cloudId);
+
+/* defined in rest.c */
+extern char *rest_choose_url(char *urls);
+
+/* the index's url option, which can be a comma-separated list of urls */
+static inline char *ZDBIndexOptionsGetUrls(Relation rel) {
 	char *url = ZDBIndexOptionsGetUrlMacro(rel);
 
+	if (ZDBIndexOptionsGetCloudId(rel) != NULL) {
+		if (strcmp("default", url) != 0)
+			ereport(ERROR,
+					(errcode(ERRCODE_INVALID_PARAMETER_VALUE),
+							errmsg("only one of the 'url' and 'cloud_id' index options can be set")));
+
+		return cloud_id_to_url(ZDBIndexOptionsGetCloudId(rel));
+	}
+
 	url = strcmp("default", url) == 0 ? zdb_default_elasticsearch_url_guc : url;
 	if (url == NULL) {
 		ereport(ERROR,
@@ -63,6 +109,18 @@ static inline char *ZDBIndexOptionsGetUrl(Relation rel) {
 	return url;
 }
 
+/* which of the index's urls the next request should use */
+static inline char *ZDBIndexOptionsGetUrl(Relation rel) {
+	return rest_choose_url(ZDBIndexOptionsGetUrls(rel));
+}
+
+/* which url the next read-only request (_search, _count, etc) should use.  Writes always use the 'url' option */
+static inline char *ZDBIndexOptionsGetSearchUrl(Relation rel) {
+	char *urls = ZDBIndexOptionsGetSearchUrls(rel);
+
+	return rest_choose_url(urls != NULL ? urls : ZDBIndexOptionsGetUrls(rel));
+}
+
 #define ZDBIndexOptionsGetTypeName(relation) \
     ((relation)->rd_options && ((ZDBIndexOptions *) (relation)->rd_options)->typeNameValueOffset > 0 ? \
       (char *) ((ZDBIndexOptions *) (relation)->rd_options) + ((ZDBIndexOptions *) (relation)->rd_options)->typeNameValueOffset : ("doc"))
@@ -83,6 +141,12 @@ static inline char *ZDBIndexOptionsGetUrl(Relation rel) {
 #define ZDBIndexOptionsGetBatchSize(relation) \
     (relation)->rd_options ? ((ZDBIndexOptions *) (relation)->rd_options)->batch_size : (8*1024*1024)
 
+#define ZDBIndexOptionsGetMaxDocumentSize(relation) \
+    ((relation)->rd_options ? ((ZDBIndexOptions *) (relation)->rd_options)->maxDocumentSize : 0)
+
+#define ZDBIndexOptionsGetMaxConcurrentShardRequests(relation) \
+    ((relation)->rd_options ? ((ZDBIndexOptions *) (relation)->rd_options)->maxConcurrentShardRequests : 0)
+
 #define ZDBIndexOptionsGetCompressionLevel(relation) \
     (relation)->rd_options ? ((ZDBIndexOptions *) (relation)->rd_options)->compressionLevel : 1
 
@@ -94,9 +158,82 @@ static inline char *ZDBIndexOptionsGetUrl(Relation rel) {
     ((relation)->rd_options && ((ZDBIndexOptions *) (relation)->rd_options)->uuidOffset > 0 ? \
       (char *) ((ZDBIndexOptions *) (relation)->rd_options) + ((ZDBIndexOptions *) (relation)->rd_options)->uuidOffset : (NULL))
 
+#define ZDBIndexOptionsGetFieldMapping(relation) \
+    ((relation)->rd_options && ((ZDBIndexOptions *) (relation)->rd_options)->fieldMappingOffset > 0 ? \
+      (char *) ((ZDBIndexOptions *) (relation)->rd_options) + ((ZDBIndexOptions *) (relation)->rd_options)->fieldMappingOffset : (NULL))
+
+#define ZDBIndexOptionsGetOnError(relation) \
+    ((relation)->rd_options && ((ZDBIndexOptions *) (relation)->rd_options)->onErrorOffset > 0 ? \
+      (char *) ((ZDBIndexOptions *) (relation)->rd_options) + ((ZDBIndexOptions *) (relation)->rd_options)->onErrorOffset : ("abort"))
+
+#define ZDBIndexOptionsGetSkipErrors(relation) \
+    (strcmp("skip", ZDBIndexOptionsGetOnError(relation)) == 0)
+
+#define ZDBIndexOptionsGetRouting(relation) \
+    ((relation)->rd_options && ((ZDBIndexOptions *) (relation)->rd_options)->routingOffset > 0 ? \
+      (char *) ((ZDBIndexOptions *) (relation)->rd_options) + ((ZDBIndexOptions *) (relation)->rd_options)->routingOffset : (NULL))
+
+#define ZDBIndexOptionsGetIncludeColumns(relation) \
+    ((relation)->rd_options && ((ZDBIndexOptions *) (relation)->rd_options)->includeColumnsOffset > 0 ? \
+      (char *) ((ZDBIndexOptions *) (relation)->rd_options) + ((ZDBIndexOptions *) (relation)->rd_options)->includeColumnsOffset : (NULL))
+
+#define ZDBIndexOptionsGetExcludeColumns(relation) \
+    ((relation)->rd_options && ((ZDBIndexOptions *) (relation)->rd_options)->excludeColumnsOffset > 0 ? \
+      (char *) ((ZDBIndexOptions *) (relation)->rd_options) + ((ZDBIndexOptions *) (relation)->rd_options)->excludeColumnsOffset : (NULL))
+
+#define ZDBIndexOptionsGetBulkPrimaryTimeout(relation) \
+    ((relation)->rd_options && ((ZDBIndexOptions *) (relation)->rd_options)->bulkPrimaryTimeoutOffset > 0 ? \
+      (char *) ((ZDBIndexOptions *) (relation)->rd_options) + ((ZDBIndexOptions *) (relation)->rd_options)->bulkPrimaryTimeoutOffset : (NULL))
+
+#define ZDBIndexOptionsGetWaitForActiveShards(relation) \
+    ((relation)->rd_options && ((ZDBIndexOptions *) (relation)->rd_options)->waitForActiveShardsOffset > 0 ? \
+      (char *) ((ZDBIndexOptions *) (relation)->rd_options) + ((ZDBIndexOptions *) (relation)->rd_options)->waitForActiveShardsOffset : (NULL))
+
+#define ZDBIndexOptionsGetDefaultAnalyzer(relation) \
+    ((relation)->rd_options && ((ZDBIndexOptions *) (relation)->rd_options)->defaultAnalyzerOffset > 0 ? \
+      (char *) ((ZDBIndexOptions *) (relation)->rd_options) + ((ZDBIndexOptions *) (relation)->rd_options)->defaultAnalyzerOffset : (NULL))
+
+#define ZDBIndexOptionsGetDotReplacement(relation) \
+    ((relation)->rd_options && ((ZDBIndexOptions *) (relation)->rd_options)->dotReplacementOffset > 0 ? \
+      (char *) ((ZDBIndexOptions *) (relation)->rd_options) + ((ZDBIndexOptions *) (relation)->rd_options)->dotReplacementOffset : (NULL))
+
+#define ZDBIndexOptionsGetTransformFunction(relation) \
+    ((relation)->rd_options && ((ZDBIndexOptions *) (relation)->rd_options)->transformFunctionOffset > 0 ? \
+      (char *) ((ZDBIndexOptions *) (relation)->rd_options) + ((ZDBIndexOptions *) (relation)->rd_options)->transformFunctionOffset : (NULL))
+
+#define ZDBIndexOptionsGetIndexSettings(relation) \
+    ((relation)->rd_options && ((ZDBIndexOptions *) (relation)->rd_options)->indexSettingsOffset > 0 ? \
+      (char *) ((ZDBIndexOptions *) (relation)->rd_options) + ((ZDBIndexOptions *) (relation)->rd_options)->indexSettingsOffset : (NULL))
+
+#define ZDBIndexOptionsGetUpdateScript(relation) \
+    ((relation)->rd_options && ((ZDBIndexOptions *) (relation)->rd_options)->updateScriptOffset > 0 ? \
+      (char *) ((ZDBIndexOptions *) (relation)->rd_options) + ((ZDBIndexOptions *) (relation)->rd_options)->updateScriptOffset : (NULL))
+
+#define ZDBIndexOptionsGetUpdateScriptParams(relation) \
+    ((relation)->rd_options && ((ZDBIndexOptions *) (relation)->rd_options)->updateScriptParamsOffset > 0 ? \
+      (char *) ((ZDBIndexOptions *) (relation)->rd_options) + ((ZDBIndexOptions *) (relation)->rd_options)->updateScriptParamsOffset : (NULL))
+
+#define ZDBIndexOptionsGetHotTuples(relation) \
+    ((relation)->rd_options && ((ZDBIndexOptions *) (relation)->rd_options)->hotTuplesOffset > 0 ? \
+      (char *) ((ZDBIndexOptions *) (relation)->rd_options) + ((ZDBIndexOptions *) (relation)->rd_options)->hotTuplesOffset : ("error"))
+
+#define ZDBIndexOptionsGetNonFiniteFloats(relation) \
+    ((relation)->rd_options && ((ZDBIndexOptions *) (relation)->rd_options)->nonfiniteFloatsOffset > 0 ? \
+      (char *) ((ZDBIndexOptions *) (relation)->rd_options) + ((ZDBIndexOptions *) (relation)->rd_options)->nonfiniteFloatsOffset : ("null"))
+
+#define ZDBIndexOptionsGetIndexAction(relation) \
+    ((relation)->rd_options && ((ZDBIndexOptions *) (relation)->rd_options)->indexActionOffset > 0 ? \
+      (char *) ((ZDBIndexOptions *) (relation)->rd_options) + ((ZDBIndexOptions *) (relation)->rd_options)->indexActionOffset : ("index"))
+
 #define ZDBIndexOptionsGetLLAPI(relation) \
     ((bool) ((relation)->rd_options ? ((ZDBIndexOptions *) (relation)->rd_options)->llapi : false))
 
+#define ZDBIndexOptionsGetAllowSharedUuid(relation) \
+    ((bool) ((relation)->rd_options ? ((ZDBIndexOptions *) (relation)->rd_options)->allowSharedUuid : false))
+
+#define ZDBIndexOptionsGetFlushAfterBuild(relation) \
+    ((bool) ((relation)->rd_options ? ((ZDBIndexOptions *) (relation)->rd_options)->flushAfterBuild : false))
+
 #define ZDBIndexOptionsGetOptimizeAfter(relation) \
     ((uint64) ((relation)->rd_options ? ((ZDBIndexOptions *) (relation)->rd_options)->optimizeAfter : 0))
 
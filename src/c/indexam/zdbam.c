@@ -16,11 +16,13 @@
 
 #include "zdbam.h"
 
+#include "elasticsearch/mapping.h"
 #include "elasticsearch/querygen.h"
 #include "highlighting/highlighting.h"
 #include "scoring/scoring.h"
 #include "indexam/define_index.h"
 #include "access/amapi.h"
+#include "access/heapam.h"
 #include "access/htup_details.h"
 #include "access/reloptions.h"
 #include "access/relscan.h"
@@ -32,9 +34,11 @@
 #include "catalog/pg_trigger.h"
 #include "catalog/toasting.h"
 #include "commands/dbcommands.h"
+#include "commands/defrem.h"
 #include "commands/event_trigger.h"
 #include "commands/tablecmds.h"
 #include "commands/trigger.h"
+#include "executor/executor.h"
 #include "executor/spi.h"
 #include "nodes/makefuncs.h"
 #include "nodes/nodeFuncs.h"
@@ -48,6 +52,10 @@
 #include "storage/procarray.h"
 #include "tcop/utility.h"
 #include "utils/lsyscache.h"
+#include "utils/tqual.h"
+#include "utils/varlena.h"
+
+#include <ctype.h>
 
 static const struct config_enum_entry zdb_log_level_options[] = {
 		{"debug",   DEBUG2,  true},
@@ -63,10 +71,19 @@ static const struct config_enum_entry zdb_log_level_options[] = {
 		{NULL, 0,            false}
 };
 
+static const struct config_enum_entry zdb_check_node_versions_options[] = {
+		{"off",     CHECK_NODE_VERSIONS_OFF,     false},
+		{"warning", CHECK_NODE_VERSIONS_WARNING, false},
+		{"error",   CHECK_NODE_VERSIONS_ERROR,   false},
+		{NULL, 0,                                false}
+};
+
 typedef struct ZDBBuildStateData {
 	double                   indtuples;
 	ElasticsearchBulkContext *esContext;
 	MemoryContext            memoryContext;
+	char                     *hotTuples;
+	uint64                   nskippedHot;
 }                                     ZDBBuildStateData;
 
 typedef struct ZDBScanContext {
@@ -81,6 +98,8 @@ typedef struct ZDBScanContext {
 
 PG_FUNCTION_INFO_V1(zdb_delete_trigger);
 PG_FUNCTION_INFO_V1(zdb_update_trigger);
+PG_FUNCTION_INFO_V1(zdb_cleanup);
+PG_FUNCTION_INFO_V1(zdb_copy_index);
 
 void zdb_aminit(void);
 bool zdbamvalidate(Oid opclassoid);
@@ -101,9 +120,9 @@ static int64 amgetbitmap(IndexScanDesc scan, TIDBitmap *tbm);
 static void zdbbuildCallback(Relation indexRel, HeapTuple htup, Datum *values, bool *isnull, bool tupleIsAlive, void *state);
 static void index_record(ElasticsearchBulkContext *esContext, MemoryContext scratchContext, ItemPointer ctid, Datum record, HeapTuple htup);
 
-static void apply_alter_statement(PlannedStmt *parsetree, char *url, uint32 shards, char *typeName, char *oldAlias, char *oldUUID);
+static void apply_alter_statement(PlannedStmt *parsetree, char *url, char *searchUrl, uint32 shards, char *typeName, char *oldAlias, char *oldUUID);
 static Relation open_relation_from_parsetree(PlannedStmt *parsetree, LOCKMODE lockmode, bool *is_index);
-static void get_immutable_index_options(PlannedStmt *parsetree, char **url, uint32 *shards, char **typeName, char **alias, char **uuid);
+static void get_immutable_index_options(PlannedStmt *parsetree, char **url, char **searchUrl, uint32 *shards, char **typeName, char **alias, char **uuid);
 
 #if (IS_PG_10)
 #define STRING_VALIDATOR_SIGNATURE char *
@@ -111,6 +130,9 @@ static void get_immutable_index_options(PlannedStmt *parsetree, char **url, uint
 #define STRING_VALIDATOR_SIGNATURE const char *
 #endif
 
+/* longest 'update_script' (and 'update_script_params') we'll accept */
+#define MAX_UPDATE_SCRIPT_LENGTH 1024
+
 /*lint -esym 715,extra,source ignore unused param */
 static bool validate_default_elasticsearch_url(char **newval, void **extra, GucSource source) {
 	/* valid only if it's NULL or ends with a forward slash */
@@ -118,14 +140,104 @@ static bool validate_default_elasticsearch_url(char **newval, void **extra, GucS
 	return str == NULL || str[strlen(str) - 1] == '/';
 }
 
+/*lint -esym 715,extra,source ignore unused param */
+static bool validate_bulk_filter_path(char **newval, void **extra, GucSource source) {
+	/* we need the top-level "errors" property to know if a _bulk request failed */
+	char *str = *newval;
+	return str != NULL && strstr(str, "errors") != NULL;
+}
+
+/*lint -esym 715,extra,source ignore unused param */
+static bool validate_cloud_auth_guc(char **newval, void **extra, GucSource source) {
+	if (*newval == NULL || (*newval)[0] == '\0' || strchr(*newval, ':') != NULL)
+		return true;
+
+	GUC_check_errdetail("It must be in the form 'username:password'.");
+	return false;
+}
+
+/*lint -esym 715,extra,source ignore unused param */
+static bool validate_http_headers(char **newval, void **extra, GucSource source) {
+	static char           *reserved[] = {"content-type", "content-encoding", "content-length"};
+	char                  *str        = *newval;
+	JsonObjectKeyIterator itr;
+
+	if (str == NULL || str[0] == '\0')
+		return true;
+
+	if (!is_json(str)) {
+		GUC_check_errdetail("It must be a json object, such as '{\"X-Tenant\": \"acme\"}'.");
+		return false;
+	}
+
+	for (itr = get_json_object_key_iterator(parse_json_object_from_string(str, CurrentMemoryContext));
+		 itr != NULL; itr = get_next_from_json_object_iterator(itr)) {
+		const char *name  = get_key_from_json_object_iterator(itr);
+		const char *value = get_string_value_from_json_object_iterator(itr);
+		const char *c;
+		int        i;
+
+		/* a header name is an HTTP "token" */
+		for (c = name; *c != '\0'; c++) {
+			if (!isalnum((unsigned char) *c) && strchr("!#$%&'*+-.^_`|~", *c) == NULL)
+				break;
+		}
+		if (c == name || *c != '\0') {
+			GUC_check_errdetail("'%s' is not a valid HTTP header name.", name);
+			return false;
+		}
+
+		for (i = 0; i < lengthof(reserved); i++) {
+			if (pg_strcasecmp(reserved[i], name) == 0) {
+				GUC_check_errdetail("The '%s' header is set by ZomboDB itself.", name);
+				return false;
+			}
+		}
+
+		if (value == NULL || strpbrk(value, "\r\n") != NULL) {
+			GUC_check_errdetail("The value of the '%s' header must be a string on a single line.", name);
+			return false;
+		}
+	}
+
+	return true;
+}
+
+static bool urls_end_in_slash(const char *str) {
+	char *copy = pstrdup(str);
+	char *url;
+
+	/* each of its comma-separated urls must end with a forward slash */
+	for (url = strtok(copy, ","); url != NULL; url = strtok(NULL, ",")) {
+		if (url[strlen(url) - 1] != '/')
+			break;
+	}
+	pfree(copy);
+
+	return url == NULL && str[strlen(str) - 1] == '/';
+}
+
 static void validate_url(STRING_VALIDATOR_SIGNATURE str) {
-	/* valid only if it ends with a forward slash or it equals the string 'default' */
-	if (str != NULL && (str[strlen(str) - 1] == '/' || strcmp("default", str) == 0))
+	/* valid only if each of its comma-separated urls ends with a forward slash or it equals the string 'default' */
+	if (str != NULL && (strcmp("default", str) == 0 || urls_end_in_slash(str)))
 		return;
 
 	elog(ERROR, "'url' index option must end in a slash");
 }
 
+static void validate_search_url(STRING_VALIDATOR_SIGNATURE str) {
+	if (str == NULL || urls_end_in_slash(str))
+		return;
+
+	elog(ERROR, "'search_url' index option must end in a slash");
+}
+
+static void validate_cloud_id(STRING_VALIDATOR_SIGNATURE str) {
+	/* decoding it complains if it's malformed */
+	if (str != NULL)
+		cloud_id_to_url(str);
+}
+
 /*lint -esym 715,str ignore unused param */
 static void validate_type_name(STRING_VALIDATOR_SIGNATURE str) {
 	/* noop */
@@ -146,11 +258,217 @@ static void validate_uuid(STRING_VALIDATOR_SIGNATURE str) {
 	/* noop */
 }
 
+static void validate_on_error(STRING_VALIDATOR_SIGNATURE str) {
+	if (str == NULL || strcmp("abort", str) == 0 || strcmp("skip", str) == 0)
+		return;
+
+	elog(ERROR, "'on_error' index option must be one of 'abort' or 'skip'");
+}
+
+/*lint -esym 715,str ignore unused param */
+static void validate_routing(STRING_VALIDATOR_SIGNATURE str) {
+	/* noop -- the column is checked when the index is built */
+}
+
+static void validate_index_action(STRING_VALIDATOR_SIGNATURE str) {
+	if (str == NULL || strcmp("index", str) == 0 || strcmp("create", str) == 0)
+		return;
+
+	elog(ERROR, "'index_action' index option must be one of 'index' or 'create'");
+}
+
+static void validate_hot_tuples(STRING_VALIDATOR_SIGNATURE str) {
+	if (str == NULL || strcmp("error", str) == 0 || strcmp("index", str) == 0 || strcmp("skip", str) == 0)
+		return;
+
+	elog(ERROR, "'hot_tuples' index option must be one of 'error', 'index', or 'skip'");
+}
+
+static void validate_nonfinite_floats(STRING_VALIDATOR_SIGNATURE str) {
+	if (str == NULL || strcmp("null", str) == 0 || strcmp("clamp", str) == 0 || strcmp("error", str) == 0)
+		return;
+
+	elog(ERROR, "'nonfinite_floats' index option must be one of 'null', 'clamp', or 'error'");
+}
+
+static void validate_bulk_primary_timeout(STRING_VALIDATOR_SIGNATURE str) {
+	const char *units;
+
+	if (str == NULL)
+		return;
+
+	/* it needs to be an Elasticsearch time value, such as "30s" or "1m" */
+	for (units = str; isdigit((unsigned char) *units); units++);
+
+	if (units != str && (strcmp("d", units) == 0 || strcmp("h", units) == 0 || strcmp("m", units) == 0 ||
+						 strcmp("s", units) == 0 || strcmp("ms", units) == 0 || strcmp("micros", units) == 0 ||
+						 strcmp("nanos", units) == 0))
+		return;
+
+	elog(ERROR, "'bulk_primary_timeout' index option must be an Elasticsearch time value, such as '30s'");
+}
+
+static void validate_wait_for_active_shards(STRING_VALIDATOR_SIGNATURE str) {
+	const char *digits;
+
+	if (str == NULL || strcmp("all", str) == 0)
+		return;
+
+	/* otherwise it needs to be a positive number of shard copies */
+	for (digits = str; isdigit((unsigned char) *digits); digits++);
+
+	if (digits != str && *digits == '\0' && atoi(str) > 0)
+		return;
+
+	elog(ERROR, "'wait_for_active_shards' index option must be 'all' or a positive number of shard copies");
+}
+
+static void validate_default_analyzer(STRING_VALIDATOR_SIGNATURE str) {
+	const char *c;
+
+	if (str == NULL)
+		return;
+
+	/* it's the name of an analyzer, which we put into the mapping as-is */
+	for (c = str; *c != '\0'; c++) {
+		if (!isalnum((unsigned char) *c) && *c != '_' && *c != '-')
+			break;
+	}
+
+	if (c != str && *c == '\0')
+		return;
+
+	elog(ERROR, "'default_analyzer' index option must be the name of an Elasticsearch analyzer, such as 'english'");
+}
+
+static void validate_dot_replacement(STRING_VALIDATOR_SIGNATURE str) {
+	if (str == NULL)
+		return;
+
+	if (strchr(str, '.') != NULL)
+		elog(ERROR, "'dot_replacement' index option must not contain a '.'");
+}
+
+static void validate_transform_function(STRING_VALIDATOR_SIGNATURE str) {
+	if (str == NULL)
+		return;
+
+	lookup_transform_function((char *) str);
+}
+
+/*
+ * Resolve a 'transform_function' option against the current search_path once, as it's set, and store
+ * the function's schema-qualified name instead, so that rows are always transformed by the same function
+ */
+static void qualify_transform_function(List *options) {
+	ListCell *lc;
+
+	foreach (lc, options) {
+		DefElem *def = lfirst(lc);
+
+		if (strcmp("transform_function", def->defname) == 0 && def->arg != NULL) {
+			Oid funcoid = lookup_transform_function(defGetString(def));
+
+			def->arg = (Node *) makeString(quote_qualified_identifier(get_namespace_name(get_func_namespace(funcoid)),
+																	  get_func_name(funcoid)));
+		}
+	}
+}
+
+static void validate_index_settings(STRING_VALIDATOR_SIGNATURE str) {
+	static char           *managed[] = {"number_of_shards", "number_of_replicas", "refresh_interval",
+										"query.default_field", "analysis"};
+	JsonObjectKeyIterator itr;
+
+	if (str == NULL)
+		return;
+
+	if (!is_json((char *) str))
+		elog(ERROR, "'index_settings' index option must be a json object");
+
+	for (itr = get_json_object_key_iterator(parse_json_object_from_string((char *) str, CurrentMemoryContext));
+		 itr != NULL; itr = get_next_from_json_object_iterator(itr)) {
+		const char *name = get_key_from_json_object_iterator(itr);
+		int        i;
+
+		if (strcmp(name, "index") == 0)
+			elog(ERROR, "'index_settings' index option must use dotted setting names, such as 'index.codec'");
+
+		if (strncmp(name, "index.", strlen("index.")) == 0)
+			name += strlen("index.");
+
+		/* these have index options of their own, or are generated by ZomboDB */
+		for (i = 0; i < lengthof(managed); i++) {
+			size_t len = strlen(managed[i]);
+
+			if (strncmp(name, managed[i], len) == 0 && (name[len] == '\0' || name[len] == '.'))
+				elog(ERROR, "'index_settings' index option must not contain the '%s' setting", managed[i]);
+		}
+	}
+}
+
+static void validate_update_script(STRING_VALIDATOR_SIGNATURE str) {
+	if (str == NULL)
+		return;
+
+	if (strlen(str) > MAX_UPDATE_SCRIPT_LENGTH)
+		elog(ERROR, "'update_script' index option must be no longer than %d characters", MAX_UPDATE_SCRIPT_LENGTH);
+
+	/*
+	 * the snippet runs with full access to each document, including the fields ZomboDB uses for visibility,
+	 * so it's trusted input that only a superuser can provide
+	 */
+	if (!superuser())
+		ereport(ERROR,
+				(errcode(ERRCODE_INSUFFICIENT_PRIVILEGE),
+						errmsg("'update_script' index option can only be set by a superuser")));
+}
+
+static void validate_update_script_params(STRING_VALIDATOR_SIGNATURE str) {
+	Jsonb      *params;
+	JsonbValue key;
+	char       *reserved[] = {"CMAX", "XMAX"};
+	int        i;
+
+	if (str == NULL)
+		return;
+
+	if (strlen(str) > MAX_UPDATE_SCRIPT_LENGTH)
+		elog(ERROR, "'update_script_params' index option must be no longer than %d characters", MAX_UPDATE_SCRIPT_LENGTH);
+
+	params = DatumGetJsonbP(DirectFunctionCall1(jsonb_in, CStringGetDatum(str)));
+	if (!JB_ROOT_IS_OBJECT(params) || JB_ROOT_IS_SCALAR(params))
+		elog(ERROR, "'update_script_params' index option must be a json object");
+
+	/* these are always provided by ZomboDB itself */
+	for (i = 0; i < lengthof(reserved); i++) {
+		key.type           = jbvString;
+		key.val.string.val = reserved[i];
+		key.val.string.len = strlen(reserved[i]);
+
+		if (findJsonbValueFromContainer(&params->root, JB_FOBJECT, &key) != NULL)
+			elog(ERROR, "'update_script_params' index option must not contain a '%s' key", reserved[i]);
+	}
+}
+
+static void validate_column_list(STRING_VALIDATOR_SIGNATURE str) {
+	List *names;
+
+	if (str != NULL && !SplitIdentifierString(pstrdup(str), ',', &names))
+		elog(ERROR, "'include_columns' and 'exclude_columns' index options must be a comma-separated list of column names");
+}
+
+static void validate_field_mapping(STRING_VALIDATOR_SIGNATURE str) {
+	/* valid only if it's a json object of json objects, keyed by column name */
+	if (str != NULL)
+		validate_field_mapping_option((char *) str);
+}
 
 PG_FUNCTION_INFO_V1(zdb_amhandler);
 
 static List                     *insert_contexts        = NULL;
 static List                     *to_drop                = NULL;
+static List                     *dropped_indexes        = NULL;
 static List                     *created_indices_urls   = NULL;
 static List                     *aborted_xids           = NULL;
 static ExecutorStart_hook_type  prev_ExecutorStartHook  = NULL;
@@ -167,6 +485,19 @@ int  zdb_default_row_estimation_guc;
 bool zdb_curl_verbose_guc;
 bool zdb_ignore_visibility_guc;
 int  zdb_default_replicas_guc;
+char *zdb_bulk_filter_path_guc;
+int  zdb_bulk_concurrency_per_node_guc;
+int  zdb_small_table_threshold_guc;
+int  zdb_batch_max_age_guc;
+int  zdb_curl_wait_timeout_guc;
+int  zdb_scroll_size_guc;
+int  zdb_slow_bulk_threshold_guc;
+int  zdb_bulk_retry_budget_guc;
+char *zdb_http_headers_guc;
+bool zdb_ssl_verify_guc;
+char *zdb_cloud_auth_guc;
+int  zdb_check_node_versions_guc;
+bool zdb_verify_build_guc;
 
 relopt_kind RELOPT_KIND_ZDB;
 
@@ -192,6 +523,21 @@ void finish_inserts(bool is_commit) {
 
 }
 
+/*
+ * Send any batch that's been accumulating rows for longer than zdb.batch_max_age.  Otherwise it
+ * would wait for the next row, which may not arrive until much later in the transaction, if ever
+ */
+static void send_aged_batches(void) {
+	ListCell *lc;
+
+	foreach (lc, insert_contexts) {
+		ZDBIndexChangeContext *context = lfirst(lc);
+
+		if (ElasticsearchBulkBatchIsTooOld(context->esContext))
+			ElasticsearchFinishBulkProcess(context->esContext, false);
+	}
+}
+
 Datum collect_used_xids(MemoryContext memoryContext) {
 	ListCell        *lc;
 	ArrayBuildState *astate = NULL;
@@ -254,6 +600,10 @@ static void xact_commit_callback(XactEvent event, void *arg) {
 				ElasticsearchDeleteIndexDirect(index_url);
 			}
 
+			foreach(lc, dropped_indexes) {
+				ElasticsearchForgetBulkStats(lfirst_oid(lc));
+			}
+
 			RESUME_INTERRUPTS();
 		}
 			break;
@@ -265,12 +615,16 @@ static void xact_commit_callback(XactEvent event, void *arg) {
 	switch (event) {
 		case XACT_EVENT_ABORT:
 		case XACT_EVENT_PARALLEL_ABORT:
+			/* a build that errored never got to finish, so it's still showing in zdb.active_builds */
+			ElasticsearchForgetActiveBuilds();
+			/* fall through */
 		case XACT_EVENT_COMMIT:
 		case XACT_EVENT_PARALLEL_COMMIT:
 		case XACT_EVENT_PREPARE:
             executor_depth       = 0;
             insert_contexts      = NULL;
             to_drop              = NULL;
+            dropped_indexes      = NULL;
             created_indices_urls = NULL;
             aborted_xids         = NULL;
             currentQueryStack    = NULL;
@@ -533,6 +887,8 @@ static void zdb_executor_end_hook(QueryDesc *queryDesc) {
 		highlight_support_cleanup();
 
 		currentQueryStack = NULL;
+
+		send_aged_batches();
 	}
 
 	push_executor_info(queryDesc);
@@ -588,6 +944,17 @@ static void zdb_executor_finish_hook(QueryDesc *queryDesc) {
 	PG_END_TRY();
 }
 
+/*
+ * Forget a dropped index's bulk stats when the transaction commits, and delete its Elasticsearch index
+ * too, unless it's used by the low-level API, whose Elasticsearch indices outlive DROP INDEX
+ */
+static void drop_on_commit(Relation indexRel) {
+	if (!ZDBIndexOptionsGetLLAPI(indexRel))
+		to_drop = lappend(to_drop, psprintf("%s%s", ZDBIndexOptionsGetUrl(indexRel),
+											ZDBIndexOptionsGetIndexName(indexRel)));
+	dropped_indexes = lappend_oid(dropped_indexes, RelationGetRelid(indexRel));
+}
+
 static void run_process_utility_hook(PlannedStmt *parsetree, const char *queryString, ProcessUtilityContext context, ParamListInfo params, QueryEnvironment *queryEnv, DestReceiver *dest, char *completionTag) {
 	/* ask Postgres to execute this utility statement */
 	if (prev_ProcessUtilityHook)
@@ -625,16 +992,28 @@ static void zdb_process_utility_hook(PlannedStmt *parsetree, const char *querySt
 						break;
 
 					case T_AlterTableStmt: {
-						char   *url;
-						uint32 shards;
-						char   *typeName;
-						char   *alias;
-						char   *uuid;
+						AlterTableStmt *stmt = (AlterTableStmt *) parsetree->utilityStmt;
+						char           *url;
+						char           *searchUrl;
+						uint32         shards;
+						char           *typeName;
+						char           *alias;
+						char           *uuid;
+						ListCell       *lc;
+
+						if (stmt->relkind == OBJECT_INDEX) {
+							foreach (lc, stmt->cmds) {
+								AlterTableCmd *cmd = lfirst(lc);
+
+								if (cmd->subtype == AT_SetRelOptions)
+									qualify_transform_function((List *) cmd->def);
+							}
+						}
 
-						get_immutable_index_options(parsetree, &url, &shards, &typeName, &alias, &uuid);
+						get_immutable_index_options(parsetree, &url, &searchUrl, &shards, &typeName, &alias, &uuid);
 						run_process_utility_hook(parsetree, queryString, context, params, queryEnv, dest,
 												 completionTag);
-						apply_alter_statement(parsetree, url, shards, typeName, alias, uuid);
+						apply_alter_statement(parsetree, url, searchUrl, shards, typeName, alias, uuid);
 					}
 						break;
 
@@ -706,14 +1085,8 @@ static void zdb_process_utility_hook(PlannedStmt *parsetree, const char *querySt
 											foreach (lc2, RelationGetIndexList(rel)) {
 												Relation indexRel = RelationIdGetRelation(lfirst_oid(lc2));
 												if (indexRel != NULL) {
-													if (index_is_zdb_index(indexRel) &&
-														!ZDBIndexOptionsGetLLAPI(indexRel)) {
-														to_drop = lappend(to_drop,
-																		  psprintf("%s%s",
-																				   ZDBIndexOptionsGetUrl(indexRel),
-																				   ZDBIndexOptionsGetIndexName(
-																						   indexRel)));
-													}
+													if (index_is_zdb_index(indexRel))
+														drop_on_commit(indexRel);
 													RelationClose(indexRel);
 												}
 											}
@@ -721,13 +1094,8 @@ static void zdb_process_utility_hook(PlannedStmt *parsetree, const char *querySt
 											break;
 
 										case OBJECT_INDEX:
-											if (index_is_zdb_index(rel) && !ZDBIndexOptionsGetLLAPI(rel)) {
-
-												to_drop = lappend(to_drop,
-																  psprintf("%s%s",
-																		   ZDBIndexOptionsGetUrl(rel),
-																		   ZDBIndexOptionsGetIndexName(rel)));
-											}
+											if (index_is_zdb_index(rel))
+												drop_on_commit(rel);
 											break;
 
 										case OBJECT_SCHEMA: {
@@ -739,13 +1107,8 @@ static void zdb_process_utility_hook(PlannedStmt *parsetree, const char *querySt
 												Relation indexRel;
 
 												indexRel = RelationIdGetRelation(oid);
-												if (index_is_zdb_index(indexRel) &&
-													!ZDBIndexOptionsGetLLAPI(indexRel)) {
-													to_drop = lappend(to_drop,
-																	  psprintf("%s%s",
-																			   ZDBIndexOptionsGetUrl(indexRel),
-																			   ZDBIndexOptionsGetIndexName(indexRel)));
-												}
+												if (index_is_zdb_index(indexRel))
+													drop_on_commit(indexRel);
 												RelationClose(indexRel);
 											}
 										}
@@ -850,6 +1213,8 @@ static void zdb_process_utility_hook(PlannedStmt *parsetree, const char *querySt
 #endif
 															 );
 
+							qualify_transform_function(stmt->options);
+
 							/* Run parse analysis ... */
 							stmt = transformIndexStmt(relid, stmt, queryString);
 
@@ -919,7 +1284,8 @@ ZDBIndexChangeContext *checkout_insert_context(Relation indexRelation) {
 		    if (context->esContext->tupdesc == NULL) {
                 tupdesc = lookup_index_tupdesc(indexRelation);
                 context->esContext->tupdesc         = CreateTupleDescCopy(tupdesc);
-                context->esContext->jsonConversions = build_json_conversions(context->esContext->tupdesc);
+                context->esContext->jsonConversions = build_json_conversions(indexRelation,
+                                                                             context->esContext->tupdesc);
                 ReleaseTupleDesc(tupdesc);
 		    }
 
@@ -952,7 +1318,7 @@ ZDBIndexChangeContext *checkout_insert_context(Relation indexRelation) {
 	context->scratch    = AllocSetContextCreate(TopTransactionContext, "aminsert scratch context",
 												ALLOCSET_DEFAULT_MINSIZE,
 												ALLOCSET_DEFAULT_INITSIZE, ALLOCSET_DEFAULT_MAXSIZE);
-	context->esContext  = ElasticsearchStartBulkProcess(indexRelation, NULL, tupdesc, false);
+	context->esContext  = ElasticsearchStartBulkProcess(indexRelation, NULL, tupdesc, false, false);
 
 	insert_contexts = lappend(insert_contexts, context);
 
@@ -1026,10 +1392,61 @@ void zdb_aminit(void) {
 	DefineCustomIntVariable("zdb.default_replicas",
 							"The default number of index replicas", NULL,
 							&zdb_default_replicas_guc, 0, 0, 32768, PGC_SIGHUP, 0, NULL, NULL, NULL);
+	DefineCustomStringVariable("zdb.bulk_filter_path",
+							   "The filter_path applied to Elasticsearch _bulk API responses", NULL,
+							   &zdb_bulk_filter_path_guc, "errors,items.*.error", PGC_USERSET, 0,
+							   validate_bulk_filter_path, NULL, NULL);
+	DefineCustomIntVariable("zdb.bulk_concurrency_per_node",
+							"The number of concurrent _bulk API requests per data node when an index's bulk_concurrency is zero",
+							NULL, &zdb_bulk_concurrency_per_node_guc, 4, 1, MAX_BULK_CONCURRENCY, PGC_USERSET, 0, NULL,
+							NULL, NULL);
+	DefineCustomIntVariable("zdb.small_table_threshold",
+							"Indices are built one _bulk request at a time for tables with fewer than this many rows",
+							NULL, &zdb_small_table_threshold_guc, 10000, 0, INT_MAX, PGC_USERSET, 0, NULL, NULL, NULL);
+	DefineCustomIntVariable("zdb.batch_max_age",
+							"How long a _bulk API batch may accumulate rows before it's sent, regardless of its size.  Zero disables",
+							NULL, &zdb_batch_max_age_guc, 0, 0, INT_MAX, PGC_USERSET, GUC_UNIT_MS, NULL, NULL, NULL);
+	DefineCustomIntVariable("zdb.curl_wait_timeout",
+							"The longest ZomboDB waits for activity on its concurrent _bulk API requests before checking on them again",
+							NULL, &zdb_curl_wait_timeout_guc, 10000, 1, INT_MAX, PGC_USERSET, GUC_UNIT_MS, NULL, NULL,
+							NULL);
+	DefineCustomIntVariable("zdb.scroll_size",
+							"The number of hits ZomboDB asks Elasticsearch for in each page of search results",
+							NULL, &zdb_scroll_size_guc, MAX_DOCS_PER_REQUEST, 1, MAX_DOCS_PER_REQUEST, PGC_USERSET, 0,
+							NULL, NULL, NULL);
+	DefineCustomIntVariable("zdb.slow_bulk_threshold",
+							"_bulk requests that take at least this long are logged at zdb.log_level.  Zero disables",
+							NULL, &zdb_slow_bulk_threshold_guc, 10000, 0, INT_MAX, PGC_USERSET, GUC_UNIT_MS, NULL, NULL,
+							NULL);
+	DefineCustomIntVariable("zdb.bulk_retry_budget",
+							"How many _bulk requests a statement may resend per minute before it gives up.  Zero is unlimited",
+							NULL, &zdb_bulk_retry_budget_guc, 0, 0, INT_MAX, PGC_USERSET, 0, NULL, NULL, NULL);
+	DefineCustomStringVariable("zdb.http_headers",
+							   "A json object of additional HTTP headers to send with every Elasticsearch request", NULL,
+							   &zdb_http_headers_guc, NULL, PGC_USERSET, 0, validate_http_headers, NULL, NULL);
+	DefineCustomStringVariable("zdb.cloud_auth",
+							   "The 'username:password' to use with indexes that set 'cloud_id'", NULL,
+							   &zdb_cloud_auth_guc, NULL, PGC_SUSET, GUC_SUPERUSER_ONLY, validate_cloud_auth_guc, NULL,
+							   NULL);
+	DefineCustomBoolVariable("zdb.ssl_verify",
+							 "Should ZomboDB verify the certificates of Elasticsearch nodes it connects to with https", NULL,
+							 &zdb_ssl_verify_guc, true, PGC_SUSET, 0, NULL, NULL, NULL);
+	DefineCustomEnumVariable("zdb.check_node_versions",
+							 "What CREATE INDEX and REINDEX do if the Elasticsearch cluster's nodes run different major versions:  'off', 'warning', or 'error'",
+							 NULL, &zdb_check_node_versions_guc, CHECK_NODE_VERSIONS_OFF,
+							 zdb_check_node_versions_options, PGC_USERSET, 0, NULL, NULL, NULL);
+	DefineCustomBoolVariable("zdb.verify_build",
+							 "Should CREATE INDEX and REINDEX count the rows Elasticsearch has once they're done", NULL,
+							 &zdb_verify_build_guc, false, PGC_USERSET, 0, NULL, NULL, NULL);
 
 	/* define the relation options for use ZDB indexes */
 	RELOPT_KIND_ZDB = add_reloption_kind();
 	add_string_reloption(RELOPT_KIND_ZDB, "url", "Server URL and port", "default", validate_url);
+	add_string_reloption(RELOPT_KIND_ZDB, "search_url",
+						 "Server URL and port for read-only requests, such as _search and _count.  Defaults to 'url'",
+						 NULL, validate_search_url);
+	add_string_reloption(RELOPT_KIND_ZDB, "cloud_id", "An Elastic Cloud id to use instead of 'url'", NULL,
+						 validate_cloud_id);
 	add_string_reloption(RELOPT_KIND_ZDB, "type_name",
 						 "What Elasticsearch index type name should ZDB use?  Default is 'doc'",
 						 "doc", validate_type_name);
@@ -1039,10 +1456,17 @@ void zdb_aminit(void) {
 	add_int_reloption(RELOPT_KIND_ZDB, "shards", "The number of shards for the index", 5, 1, 32768);
 	add_int_reloption(RELOPT_KIND_ZDB, "replicas", "The number of replicas for the index",
 					  zdb_default_replicas_guc, 0, 32768);
-	add_int_reloption(RELOPT_KIND_ZDB, "bulk_concurrency", "The maximum number of concurrent _bulk API requests", 12,
-					  1, MAX_BULK_CONCURRENCY);
+	add_int_reloption(RELOPT_KIND_ZDB, "bulk_concurrency",
+					  "The maximum number of concurrent _bulk API requests.  Zero scales it with the cluster's data nodes",
+					  12, 0, MAX_BULK_CONCURRENCY);
 	add_int_reloption(RELOPT_KIND_ZDB, "batch_size", "The size in bytes of batch calls to the _bulk API",
 					  1024 * 1024 * 8, 1024, (INT32_MAX / 2) - 1);
+	add_int_reloption(RELOPT_KIND_ZDB, "max_document_size",
+					  "The largest document, in bytes, to send to Elasticsearch.  Zero means there's no limit", 0, 0,
+					  INT32_MAX);
+	add_int_reloption(RELOPT_KIND_ZDB, "max_concurrent_shard_requests",
+					  "How many shards a search may query at once on each node.  Zero uses Elasticsearch's default", 0, 0,
+					  INT32_MAX);
 	add_int_reloption(RELOPT_KIND_ZDB, "compression_level", "0-9 value to indicate the level of HTTP compression", 1,
 					  0, 9);
 	add_string_reloption(RELOPT_KIND_ZDB, "alias", "The Elasticsearch Alias to which this index should belong", NULL,
@@ -1051,6 +1475,55 @@ void zdb_aminit(void) {
 	add_int_reloption(RELOPT_KIND_ZDB, "optimize_after",
 					  "After how many deleted docs should ZDB _optimize the ES index during VACUUM?", 0, 0, INT32_MAX);
 	add_bool_reloption(RELOPT_KIND_ZDB, "llapi", "Will this index be used by ZomboDB's low-level API?", false);
+	add_bool_reloption(RELOPT_KIND_ZDB, "allow_shared_uuid",
+					   "Can this index use the same Elasticsearch index as another ZomboDB index?", false);
+	add_bool_reloption(RELOPT_KIND_ZDB, "flush_after_build",
+					   "Should building the index flush Elasticsearch's translog into Lucene segments?", false);
+	add_string_reloption(RELOPT_KIND_ZDB, "field_mapping",
+						 "A json object of Elasticsearch mapping definitions, keyed by column name, that override the generated mapping",
+						 NULL, validate_field_mapping);
+	add_string_reloption(RELOPT_KIND_ZDB, "on_error",
+						 "What to do when Elasticsearch rejects a document:  'abort' or 'skip'",
+						 "abort", validate_on_error);
+	add_string_reloption(RELOPT_KIND_ZDB, "routing",
+						 "The name of the column whose value is used as each document's Elasticsearch routing value",
+						 NULL, validate_routing);
+	add_string_reloption(RELOPT_KIND_ZDB, "index_action",
+						 "The _bulk API action used to add new documents:  'index' or 'create'",
+						 "index", validate_index_action);
+	add_string_reloption(RELOPT_KIND_ZDB, "include_columns",
+						 "A comma-separated list of the only columns to index", NULL, validate_column_list);
+	add_string_reloption(RELOPT_KIND_ZDB, "exclude_columns",
+						 "A comma-separated list of columns not to index", NULL, validate_column_list);
+	add_string_reloption(RELOPT_KIND_ZDB, "bulk_primary_timeout",
+						 "How long Elasticsearch waits for unavailable primary shards during a _bulk request, such as '30s'",
+						 NULL, validate_bulk_primary_timeout);
+	add_string_reloption(RELOPT_KIND_ZDB, "wait_for_active_shards",
+						 "How many copies of each shard must be active before a _bulk request proceeds:  'all' or a number",
+						 NULL, validate_wait_for_active_shards);
+	add_string_reloption(RELOPT_KIND_ZDB, "default_analyzer",
+						 "The analyzer to use for 'text' columns, such as 'english'", NULL, validate_default_analyzer);
+	add_string_reloption(RELOPT_KIND_ZDB, "dot_replacement",
+						 "What to replace the dots in column names with, so Elasticsearch doesn't treat them as object paths",
+						 NULL, validate_dot_replacement);
+	add_string_reloption(RELOPT_KIND_ZDB, "transform_function",
+						 "A function, taking and returning jsonb, that each document is passed through before it's indexed",
+						 NULL, validate_transform_function);
+	add_string_reloption(RELOPT_KIND_ZDB, "index_settings",
+						 "A json object of extra Elasticsearch index settings, such as '{\"index.codec\": \"best_compression\"}'",
+						 NULL, validate_index_settings);
+	add_string_reloption(RELOPT_KIND_ZDB, "update_script",
+						 "A painless snippet to run, after ZomboDB's own, when a row is updated or deleted", NULL,
+						 validate_update_script);
+	add_string_reloption(RELOPT_KIND_ZDB, "update_script_params",
+						 "A json object of extra params for the 'update_script' snippet", NULL,
+						 validate_update_script_params);
+	add_string_reloption(RELOPT_KIND_ZDB, "hot_tuples",
+						 "What to do with Heap Only Tuples (HOT) found while building the index:  'error', 'index', or 'skip'",
+						 "error", validate_hot_tuples);
+	add_string_reloption(RELOPT_KIND_ZDB, "nonfinite_floats",
+						 "What to index for NaN and +/- Infinity float4/float8 values:  'null', 'clamp', or 'error'",
+						 "null", validate_nonfinite_floats);
 
 	/* register xact callbacks and planner hooks */
 	RegisterXactCallback(xact_commit_callback, NULL);
@@ -1128,6 +1601,7 @@ static IndexBuildResult *ambuild(Relation heapRelation, Relation indexRelation,
 	TupleDesc         tupdesc;
 	char              *aliasName = ZDBIndexOptionsGetAlias(indexRelation);
 	char              *indexName;
+	uint64            deadLetters;
 
 	if (already_has_zdb_index(heapRelation, indexRelation)) {
 		ereport(ERROR,
@@ -1161,6 +1635,27 @@ static IndexBuildResult *ambuild(Relation heapRelation, Relation indexRelation,
 	if (tupdesc == NULL)
 		goto definition_error;
 
+	/*
+	 * An index with a 'uuid' of its own (re)creates that Elasticsearch index, so make sure we won't
+	 * be deleting one that another ZomboDB index is using
+	 */
+	if (!ZDBIndexOptionsGetAllowSharedUuid(indexRelation)) {
+		char *other = lookup_zdb_index_sharing_uuid(indexRelation);
+
+		if (other != NULL)
+			ereport(ERROR,
+					(errcode(ERRCODE_DUPLICATE_OBJECT),
+							errmsg("ZomboDB index '%s' already uses Elasticsearch index '%s'", other,
+								   ZDBIndexOptionsGetIndexName(indexRelation)),
+							errhint("Set 'allow_shared_uuid' to true on this index if that's intended")));
+	}
+
+	/*
+	 * Make sure Elasticsearch is usable before we delete any existing index
+	 */
+	ElasticsearchCheckClusterHealth(indexRelation);
+	ElasticsearchCheckNodeVersions(indexRelation);
+
 	/*
 	 * Create the remote elasticsearch index
 	 */
@@ -1182,7 +1677,10 @@ static IndexBuildResult *ambuild(Relation heapRelation, Relation indexRelation,
 	buildstate.memoryContext = AllocSetContextCreate(CurrentMemoryContext, "zdbBuildCallback",
 													 ALLOCSET_DEFAULT_MINSIZE,
 													 ALLOCSET_DEFAULT_INITSIZE, ALLOCSET_DEFAULT_MAXSIZE);
-	buildstate.esContext     = ElasticsearchStartBulkProcess(indexRelation, indexName, tupdesc, false);
+	buildstate.esContext     = ElasticsearchStartBulkProcess(indexRelation, indexName, tupdesc, false, true);
+	buildstate.hotTuples     = ZDBIndexOptionsGetHotTuples(indexRelation);
+	buildstate.nskippedHot   = 0;
+	deadLetters              = ElasticsearchBulkDeadLetters(RelationGetRelid(indexRelation));
 
 	/*
 	 * Now we insert data into our index
@@ -1194,8 +1692,34 @@ static IndexBuildResult *ambuild(Relation heapRelation, Relation indexRelation,
 #endif
 	ElasticsearchFinishBulkProcess(buildstate.esContext, true);
 
+	if (zdb_verify_build_guc) {
+		uint64 nindexed;
+
+		/*
+		 * Make sure Elasticsearch has every row we sent it, less any it rejected that we were told to skip
+		 */
+		deadLetters = ElasticsearchBulkDeadLetters(RelationGetRelid(indexRelation)) - deadLetters;
+		nindexed    = ElasticsearchCountIndexedRows(indexRelation);
+		if (nindexed + deadLetters != (uint64) buildstate.indtuples)
+			ereport(WARNING,
+					(errcode(ERRCODE_DATA_EXCEPTION),
+							errmsg("Elasticsearch has %lu rows for index '%s', but %lu were sent to it and %lu of those skipped",
+								   nindexed, RelationGetRelationName(indexRelation), (uint64) buildstate.indtuples,
+								   deadLetters),
+							errhint("Rows may be missing from the index.  Consider a REINDEX")));
+	}
+
+	if (buildstate.nskippedHot > 0)
+		ereport(WARNING,
+				(errcode(ERRCODE_DATA_EXCEPTION),
+						errmsg("skipped %lu Heap Only Tuples (HOT) while building index '%s'", buildstate.nskippedHot,
+							   RelationGetRelationName(indexRelation)),
+						errhint("Run VACUUM FULL on the table and REINDEX the index to include them")));
+
 	/* Finish up with elasticsearch index creation */
 	ElasticsearchFinalizeIndexCreation(indexRelation);
+	if (ZDBIndexOptionsGetFlushAfterBuild(indexRelation))
+		ElasticsearchFlushIndex(indexRelation);
 
 	/*
 	 * Attach UPDATE/DELETE triggers so we can track modified rows
@@ -1239,11 +1763,20 @@ static void zdbbuildCallback(Relation indexRel, HeapTuple htup, Datum *values, b
 		return;
 
 	if (HeapTupleIsHeapOnly(htup)) {
-		ereport(ERROR,
-				(errcode(ERRCODE_DATA_EXCEPTION),
-						errmsg("Heap Only Tuple (HOT) found at (%u, %u).  Run VACUUM FULL <tablename>; and then create the index",
-							   ItemPointerGetBlockNumber(&(htup->t_self)),
-							   ItemPointerGetOffsetNumber(&(htup->t_self)))));
+		/*
+		 * Postgres hands us HOT tuples with the ctid of their chain's root tuple, which
+		 * is what an index scan needs, so with 'index' we use that as the document's _id
+		 */
+		if (strcmp("skip", buildstate->hotTuples) == 0) {
+			buildstate->nskippedHot++;
+			return;
+		} else if (strcmp("index", buildstate->hotTuples) != 0) {
+			ereport(ERROR,
+					(errcode(ERRCODE_DATA_EXCEPTION),
+							errmsg("Heap Only Tuple (HOT) found at (%u, %u).  Run VACUUM FULL <tablename>; and then create the index",
+								   ItemPointerGetBlockNumber(&(htup->t_self)),
+								   ItemPointerGetOffsetNumber(&(htup->t_self)))));
+		}
 	}
 
 	if (ZDBIndexOptionsGetLLAPI(indexRel)) {
@@ -1303,9 +1836,110 @@ static bool aminsert(Relation indexRelation, Datum *values, bool *isnull, ItemPo
 	return true;
 }
 
+/*
+ * Returns the text form of the routing column's value in 'record', a row of the index's type, or NULL
+ * if the value is null
+ */
+static char *lookup_routing_value(char *column, Datum record, TupleDesc tupdesc) {
+	HeapTupleHeader td    = DatumGetHeapTupleHeader(record);
+	int             attno = SPI_fnumber(tupdesc, column);
+	HeapTupleData   tmptup;
+
+	if (attno <= 0) {
+		ereport(ERROR,
+				(errcode(ERRCODE_UNDEFINED_COLUMN),
+						errmsg("'routing' index option references unknown column '%s'", column)));
+	}
+
+	tmptup.t_len  = HeapTupleHeaderGetDatumLength(td);
+	tmptup.t_data = td;
+	return SPI_getvalue(&tmptup, tupdesc, attno);
+}
+
+/*
+ * Like lookup_routing_value(), but for a tuple from the heap.  The index's expression is evaluated
+ * over it first, so the routing column is found the same way it was when the row was indexed
+ */
+static char *lookup_heap_routing_value(char *column, Relation heapRel, Relation indexRel, HeapTuple tuple, TupleDesc tupdesc) {
+	IndexInfo      *indexInfo = BuildIndexInfo(indexRel);
+	EState         *estate    = CreateExecutorState();
+	TupleTableSlot *slot      = MakeSingleTupleTableSlot(RelationGetDescr(heapRel));
+	Datum          values[INDEX_MAX_KEYS];
+	bool           isnull[INDEX_MAX_KEYS];
+	char           *routing   = NULL;
+
+	ExecStoreTuple(tuple, slot, InvalidBuffer, false);
+	GetPerTupleExprContext(estate)->ecxt_scantuple = slot;
+	FormIndexDatum(indexInfo, slot, estate, values, isnull);
+
+	if (!isnull[1])
+		routing = lookup_routing_value(column, values[1], tupdesc);
+
+	ExecDropSingleTupleTableSlot(slot);
+	FreeExecutorState(estate);
+
+	return routing;
+}
+
+/*
+ * The routing value of the document for the row at 'ctid', or NULL if the index doesn't use the
+ * 'routing' option, the row's value is null, or there's no such row
+ */
+char *lookup_routing_for_ctid(Relation indexRel, ItemPointer ctid) {
+	char          *column  = ZDBIndexOptionsGetRouting(indexRel);
+	char          *routing = NULL;
+	Relation      heapRel;
+	HeapTupleData tuple;
+	Buffer        buffer;
+
+	if (column == NULL)
+		return NULL;
+
+	heapRel = relation_open(IndexGetRelation(RelationGetRelid(indexRel), false), AccessShareLock);
+
+	/* the row may have since been deleted, but its document is still in the index */
+	ItemPointerCopy(ctid, &tuple.t_self);
+	if (ItemPointerGetBlockNumber(ctid) < RelationGetNumberOfBlocks(heapRel) &&
+		heap_fetch(heapRel, SnapshotAny, &tuple, &buffer, false, NULL)) {
+		TupleDesc tupdesc = lookup_index_tupdesc(indexRel);
+
+		routing = lookup_heap_routing_value(column, heapRel, indexRel, &tuple, tupdesc);
+
+		ReleaseTupleDesc(tupdesc);
+		ReleaseBuffer(buffer);
+	}
+
+	relation_close(heapRel, AccessShareLock);
+	return routing;
+}
+
+/*
+ * Replace a row's json with what the index's 'transform_function' returns for it
+ */
+static void transform_document(ElasticsearchBulkContext *esContext, StringInfo json) {
+	FunctionCallInfoData fcinfo;
+	Datum                result;
+	Jsonb                *jb;
+
+	InitFunctionCallInfoData(fcinfo, esContext->transformFunction, 1, InvalidOid, NULL, NULL);
+	fcinfo.arg[0]     = DirectFunctionCall1(jsonb_in, CStringGetDatum(json->data));
+	fcinfo.argnull[0] = false;
+	result = FunctionCallInvoke(&fcinfo);
+
+	if (fcinfo.isnull || !JB_ROOT_IS_OBJECT((jb = DatumGetJsonbP(result))) || JB_ROOT_COUNT(jb) == 0)
+		ereport(ERROR,
+				(errcode(ERRCODE_DATA_EXCEPTION),
+						errmsg("'transform_function' of index %s must return a non-empty json object",
+							   esContext->pgIndexName)));
+
+	resetStringInfo(json);
+	JsonbToCString(json, &jb->root, VARSIZE(jb));
+}
+
 static void index_record(ElasticsearchBulkContext *esContext, MemoryContext scratchContext, ItemPointer ctid, Datum record, HeapTuple htup) {
 	MemoryContext  oldContext;
 	StringInfoData json;
+	char           *routing = NULL;
 	CommandId      cmin;
 	CommandId      cmax;
 	uint64         xmin;
@@ -1318,6 +1952,10 @@ static void index_record(ElasticsearchBulkContext *esContext, MemoryContext scra
 	oldContext = MemoryContextSwitchTo(scratchContext);
 	initStringInfo(&json);
 	zdb_row_to_json(&json, record, esContext->tupdesc, esContext->jsonConversions);
+	if (esContext->transformFunction != NULL)
+		transform_document(esContext, &json);
+	if (esContext->routing != NULL)
+		routing = lookup_routing_value(esContext->routing, record, esContext->tupdesc);
 	MemoryContextSwitchTo(oldContext);
 
 	if (htup == NULL) {
@@ -1329,15 +1967,25 @@ static void index_record(ElasticsearchBulkContext *esContext, MemoryContext scra
 		xmax = InvalidTransactionId;
 	} else {
 		/* it's from a CREATE INDEX statement */
-		cmin = HeapTupleHeaderGetRawCommandId(htup->t_data);
-		cmax = HeapTupleHeaderGetRawCommandId(htup->t_data);
+		if (!HeapTupleHeaderXminFrozen(htup->t_data) &&
+			TransactionIdIsCurrentTransactionId(HeapTupleHeaderGetRawXmin(htup->t_data))) {
+			/* resolves a combo cid if the tuple was also updated/deleted by this transaction */
+			cmin = HeapTupleHeaderGetCmin(htup->t_data);
+		} else {
+			/*
+			 * the command id of a tuple from some other (or a frozen) transaction is meaningless to us,
+			 * and the raw value stored in the header could just as easily be its xvac or a combo cid
+			 */
+			cmin = FirstCommandId;
+		}
+		cmax = cmin;
 
 		xmin = convert_xid(HeapTupleHeaderGetXmin(htup->t_data));
 		xmax = InvalidTransactionId;
 	}
 
 	/* add the row to Elasticsearch */
-	ElasticsearchBulkInsertRow(esContext, ctid, &json, cmin, cmax, xmin, xmax);
+	ElasticsearchBulkInsertRow(esContext, ctid, routing, &json, cmin, cmax, xmin, xmax);
 
 	/*
 	 * and now that we've used the json value, free the MemoryContext in which it was allocated.
@@ -1390,7 +2038,7 @@ static IndexBulkDeleteResult *zdb_vacuum_internal(IndexVacuumInfo *info, IndexBu
 					pfree(tmp);
 				}
 
-				bulk = ElasticsearchStartBulkProcess(info->index, NULL, NULL, true);
+				bulk = ElasticsearchStartBulkProcess(info->index, NULL, NULL, true, false);
 
 				/*
 				 * Find all rows with what we think is an *aborted* xmin
@@ -1414,7 +2062,7 @@ static IndexBulkDeleteResult *zdb_vacuum_internal(IndexVacuumInfo *info, IndexBu
 
 					if (TransactionIdPrecedes(xmin, oldestXmin) && TransactionIdDidAbort(xmin) &&
 						!TransactionIdDidCommit(xmin) && !TransactionIdIsInProgress(xmin)) {
-						ElasticsearchBulkDeleteRowByXmin(bulk, _id, convert_xid(xmin));
+						ElasticsearchBulkDeleteRowByXmin(bulk, _id, scroll->routing, convert_xid(xmin));
 						deleted++;
 					}
 				}
@@ -1442,7 +2090,7 @@ static IndexBulkDeleteResult *zdb_vacuum_internal(IndexVacuumInfo *info, IndexBu
 
 					if (TransactionIdPrecedes(xmax, oldestXmin) && TransactionIdDidCommit(xmax) &&
 						!TransactionIdDidAbort(xmax) && !TransactionIdIsInProgress(xmax)) {
-						ElasticsearchBulkDeleteRowByXmax(bulk, _id, convert_xid(xmax));
+						ElasticsearchBulkDeleteRowByXmax(bulk, _id, scroll->routing, convert_xid(xmax));
 						deleted++;
 					}
 				}
@@ -1472,7 +2120,7 @@ static IndexBulkDeleteResult *zdb_vacuum_internal(IndexVacuumInfo *info, IndexBu
 
 					if (TransactionIdPrecedes(xmax, oldestXmin) && TransactionIdDidAbort(xmax) &&
 						!TransactionIdDidCommit(xmax) && !TransactionIdIsInProgress(xmax)) {
-						ElasticsearchBulkVacuumXmax(bulk, _id, xmax64);
+						ElasticsearchBulkVacuumXmax(bulk, _id, scroll->routing, xmax64);
 						xmaxes_reset++;
 					}
 				}
@@ -1566,6 +2214,77 @@ static IndexBulkDeleteResult *amvacuumcleanup(IndexVacuumInfo *info, IndexBulkDe
 	return stats;
 }
 
+/*
+ * Remove dead documents from the Elasticsearch index just as VACUUM would, but without
+ * having to vacuum the heap.  Returns the number of documents deleted
+ */
+Datum zdb_cleanup(PG_FUNCTION_ARGS) {
+	Oid                   indexRelId = PG_GETARG_OID(0);
+	IndexVacuumInfo       info;
+	IndexBulkDeleteResult *stats     = palloc0(sizeof(IndexBulkDeleteResult));
+
+	memset(&info, 0, sizeof(IndexVacuumInfo));
+	info.index = zdb_open_index(indexRelId, RowExclusiveLock);
+
+	zdb_vacuum_internal(&info, stats, false);
+
+	relation_close(info.index, RowExclusiveLock);
+
+	PG_RETURN_INT64((int64) stats->tuples_removed);
+}
+
+/*
+ * Scan the index's table exactly as CREATE INDEX would, sending every live row to the index's existing
+ * Elasticsearch index.  Nothing is deleted or (re)mapped first, so rows already there are simply replaced
+ */
+Datum zdb_copy_index(PG_FUNCTION_ARGS) {
+	Oid               indexRelId = PG_GETARG_OID(0);
+	Relation          indexRel;
+	Relation          heapRel;
+	IndexInfo         *indexInfo;
+	TupleDesc         tupdesc;
+	ZDBBuildStateData buildstate;
+
+	/* same lock as CREATE INDEX, so the table can't change underneath us */
+	heapRel   = heap_open(IndexGetRelation(indexRelId, false), ShareLock);
+	indexRel  = zdb_open_index(indexRelId, AccessShareLock);
+	indexInfo = BuildIndexInfo(indexRel);
+	tupdesc   = extract_tuple_desc_from_index_expressions(indexInfo);
+	if (tupdesc == NULL)
+		elog(ERROR, "unable to determine the row type of index '%s'", RelationGetRelationName(indexRel));
+
+	buildstate.indtuples     = 0;
+	buildstate.memoryContext = AllocSetContextCreate(CurrentMemoryContext, "zdbCopyIndex",
+													 ALLOCSET_DEFAULT_MINSIZE,
+													 ALLOCSET_DEFAULT_INITSIZE, ALLOCSET_DEFAULT_MAXSIZE);
+	buildstate.esContext     = ElasticsearchStartBulkProcess(indexRel, NULL, tupdesc, false, true);
+	buildstate.hotTuples     = ZDBIndexOptionsGetHotTuples(indexRel);
+	buildstate.nskippedHot   = 0;
+	ReleaseTupleDesc(tupdesc);
+
+#if (IS_PG_10)
+	IndexBuildHeapScan(heapRel, indexRel, indexInfo, false, zdbbuildCallback, &buildstate);
+#elif (IS_PG_11)
+	IndexBuildHeapScan(heapRel, indexRel, indexInfo, false, zdbbuildCallback, &buildstate, NULL);
+#endif
+	ElasticsearchFinishBulkProcess(buildstate.esContext, true);
+	MemoryContextDelete(buildstate.memoryContext);
+	if (ZDBIndexOptionsGetFlushAfterBuild(indexRel))
+		ElasticsearchFlushIndex(indexRel);
+
+	if (buildstate.nskippedHot > 0)
+		ereport(WARNING,
+				(errcode(ERRCODE_DATA_EXCEPTION),
+						errmsg("skipped %lu Heap Only Tuples (HOT) while copying to index '%s'", buildstate.nskippedHot,
+							   RelationGetRelationName(indexRel)),
+						errhint("Run VACUUM FULL on the table and REINDEX the index to include them")));
+
+	relation_close(indexRel, AccessShareLock);
+	heap_close(heapRel, ShareLock);
+
+	PG_RETURN_INT64((int64) buildstate.indtuples);
+}
+
 /*lint -esym 715,root,loop_count ignore unused param */
 static void amcostestimate(struct PlannerInfo *root, struct IndexPath *path, double loop_count, Cost *indexStartupCost, Cost *indexTotalCost, Selectivity *indexSelectivity, double *indexCorrelation, double *indexPages) {
 	Relation indexRel = RelationIdGetRelation(path->indexinfo->indexoid);
@@ -1619,17 +2338,39 @@ static bytea *amoptions(Datum reloptions, bool validate) {
 	int                           i;
 	static const relopt_parse_elt tab[] = {
 			{"url",               RELOPT_TYPE_STRING, offsetof(ZDBIndexOptions, urlValueOffset)},
+			{"search_url",        RELOPT_TYPE_STRING, offsetof(ZDBIndexOptions, searchUrlOffset)},
 			{"type_name",         RELOPT_TYPE_STRING, offsetof(ZDBIndexOptions, typeNameValueOffset)},
 			{"refresh_interval",  RELOPT_TYPE_STRING, offsetof(ZDBIndexOptions, refreshIntervalOffset)},
 			{"shards",            RELOPT_TYPE_INT,    offsetof(ZDBIndexOptions, shards)},
 			{"replicas",          RELOPT_TYPE_INT,    offsetof(ZDBIndexOptions, replicas)},
 			{"bulk_concurrency",  RELOPT_TYPE_INT,    offsetof(ZDBIndexOptions, bulk_concurrency)},
 			{"batch_size",        RELOPT_TYPE_INT,    offsetof(ZDBIndexOptions, batch_size)},
+			{"max_document_size", RELOPT_TYPE_INT,    offsetof(ZDBIndexOptions, maxDocumentSize)},
+			{"max_concurrent_shard_requests", RELOPT_TYPE_INT, offsetof(ZDBIndexOptions, maxConcurrentShardRequests)},
 			{"compression_level", RELOPT_TYPE_INT,    offsetof(ZDBIndexOptions, compressionLevel)},
 			{"alias",             RELOPT_TYPE_STRING, offsetof(ZDBIndexOptions, aliasOffset)},
 			{"optimize_after",    RELOPT_TYPE_INT,    offsetof(ZDBIndexOptions, optimizeAfter)},
 			{"llapi",             RELOPT_TYPE_BOOL,   offsetof(ZDBIndexOptions, llapi)},
 			{"uuid",              RELOPT_TYPE_STRING, offsetof(ZDBIndexOptions, uuidOffset)},
+			{"field_mapping",     RELOPT_TYPE_STRING, offsetof(ZDBIndexOptions, fieldMappingOffset)},
+			{"on_error",          RELOPT_TYPE_STRING, offsetof(ZDBIndexOptions, onErrorOffset)},
+			{"routing",           RELOPT_TYPE_STRING, offsetof(ZDBIndexOptions, routingOffset)},
+			{"index_action",      RELOPT_TYPE_STRING, offsetof(ZDBIndexOptions, indexActionOffset)},
+			{"include_columns",   RELOPT_TYPE_STRING, offsetof(ZDBIndexOptions, includeColumnsOffset)},
+			{"exclude_columns",   RELOPT_TYPE_STRING, offsetof(ZDBIndexOptions, excludeColumnsOffset)},
+			{"bulk_primary_timeout", RELOPT_TYPE_STRING, offsetof(ZDBIndexOptions, bulkPrimaryTimeoutOffset)},
+			{"hot_tuples",        RELOPT_TYPE_STRING, offsetof(ZDBIndexOptions, hotTuplesOffset)},
+			{"cloud_id",          RELOPT_TYPE_STRING, offsetof(ZDBIndexOptions, cloudIdOffset)},
+			{"allow_shared_uuid", RELOPT_TYPE_BOOL,   offsetof(ZDBIndexOptions, allowSharedUuid)},
+			{"flush_after_build", RELOPT_TYPE_BOOL,   offsetof(ZDBIndexOptions, flushAfterBuild)},
+			{"wait_for_active_shards", RELOPT_TYPE_STRING, offsetof(ZDBIndexOptions, waitForActiveShardsOffset)},
+			{"default_analyzer",  RELOPT_TYPE_STRING, offsetof(ZDBIndexOptions, defaultAnalyzerOffset)},
+			{"dot_replacement",   RELOPT_TYPE_STRING, offsetof(ZDBIndexOptions, dotReplacementOffset)},
+			{"update_script", RELOPT_TYPE_STRING, offsetof(ZDBIndexOptions, updateScriptOffset)},
+			{"transform_function", RELOPT_TYPE_STRING, offsetof(ZDBIndexOptions, transformFunctionOffset)},
+			{"index_settings", RELOPT_TYPE_STRING, offsetof(ZDBIndexOptions, indexSettingsOffset)},
+			{"update_script_params", RELOPT_TYPE_STRING, offsetof(ZDBIndexOptions, updateScriptParamsOffset)},
+			{"nonfinite_floats",  RELOPT_TYPE_STRING, offsetof(ZDBIndexOptions, nonfiniteFloatsOffset)},
 	};
 
 	options = parseRelOptions(reloptions, validate, RELOPT_KIND_ZDB, &numoptions);
@@ -1870,18 +2611,47 @@ static void amendscan(IndexScanDesc scan) {
 	pfree(scan->opaque);
 }
 
-static void handle_trigger(Oid indexRelId, ItemPointer targetCtid) {
+/*
+ * Find the ctid of the root of the HOT chain the specified tuple belongs to
+ */
+static void lookup_hot_root(Relation heapRel, ItemPointer ctid, ItemPointer root) {
+	OffsetNumber root_offsets[MaxHeapTuplesPerPage];
+	Buffer       buffer = ReadBuffer(heapRel, ItemPointerGetBlockNumber(ctid));
+
+	LockBuffer(buffer, BUFFER_LOCK_SHARE);
+	heap_get_root_tuples(BufferGetPage(buffer), root_offsets);
+	LockBuffer(buffer, BUFFER_LOCK_UNLOCK);
+	ReleaseBuffer(buffer);
+
+	ItemPointerCopy(ctid, root);
+	if (root_offsets[ItemPointerGetOffsetNumber(ctid) - 1] != InvalidOffsetNumber)
+		ItemPointerSetOffsetNumber(root, root_offsets[ItemPointerGetOffsetNumber(ctid) - 1]);
+}
+
+static void handle_trigger(Oid indexRelId, TriggerData *trigdata) {
 	MemoryContext         oldContext;
 	ZDBIndexChangeContext *context;
 	Relation              indexRel;
+	char                  *routing = NULL;
+	ItemPointerData       ctid;
 
 	oldContext = MemoryContextSwitchTo(TopTransactionContext);
 	indexRel   = RelationIdGetRelation(indexRelId);
 
 	context = checkout_insert_context(indexRel);
 
-	ElasticsearchBulkUpdateTuple(context->esContext, targetCtid, NULL, GetCurrentCommandId(true),
-								 convert_xid(GetCurrentTransactionId()));
+	/* the old version of the row lives on the shard picked by its routing value */
+	if (context->esContext->routing != NULL)
+		routing = lookup_heap_routing_value(context->esContext->routing, trigdata->tg_relation, indexRel,
+											trigdata->tg_trigtuple, context->esContext->tupdesc);
+
+	/* HOT tuples indexed with hot_tuples='index' live in Elasticsearch under their chain's root ctid */
+	ItemPointerCopy(&trigdata->tg_trigtuple->t_self, &ctid);
+	if (HeapTupleIsHeapOnly(trigdata->tg_trigtuple) && strcmp("index", ZDBIndexOptionsGetHotTuples(indexRel)) == 0)
+		lookup_hot_root(trigdata->tg_relation, &trigdata->tg_trigtuple->t_self, &ctid);
+
+	ElasticsearchBulkUpdateTuple(context->esContext, &ctid, NULL, routing,
+								 GetCurrentCommandId(true), convert_xid(GetCurrentTransactionId()));
 
 	RelationClose(indexRel);
 	MemoryContextSwitchTo(oldContext);
@@ -1905,7 +2675,7 @@ Datum zdb_delete_trigger(PG_FUNCTION_ARGS) {
 		elog(ERROR, "zdb_delete_trigger: called with incorrect number of arguments");
 
 	indexRelId = DatumGetObjectId(DirectFunctionCall1(oidin, CStringGetDatum(trigdata->tg_trigger->tgargs[0])));
-	handle_trigger(indexRelId, &trigdata->tg_trigtuple->t_self);
+	handle_trigger(indexRelId, trigdata);
 
 	return PointerGetDatum(trigdata->tg_trigtuple);
 }
@@ -1928,12 +2698,12 @@ Datum zdb_update_trigger(PG_FUNCTION_ARGS) {
 		elog(ERROR, "zdb_update_trigger: called with incorrect number of arguments");
 
 	indexRelId = DatumGetObjectId(DirectFunctionCall1(oidin, CStringGetDatum(trigdata->tg_trigger->tgargs[0])));
-	handle_trigger(indexRelId, &trigdata->tg_trigtuple->t_self);
+	handle_trigger(indexRelId, trigdata);
 
 	return PointerGetDatum(trigdata->tg_newtuple);
 }
 
-static void apply_alter_statement(PlannedStmt *parsetree, char *url, uint32 shards, char *typeName, char *oldAlias, char *oldUUID) {
+static void apply_alter_statement(PlannedStmt *parsetree, char *url, char *searchUrl, uint32 shards, char *typeName, char *oldAlias, char *oldUUID) {
 	LOCKMODE lockmode = AccessShareLock;
 	if (shards > 0) {
 		/*
@@ -1941,6 +2711,7 @@ static void apply_alter_statement(PlannedStmt *parsetree, char *url, uint32 shar
 		 * a warning, and also push any changed index settings to the backing ES index
 		 */
 		char     *newurl;
+		char     *newSearchUrl;
 		uint32   newshards;
 		char     *newTypeName;
 		char     *newalias;
@@ -1950,13 +2721,18 @@ static void apply_alter_statement(PlannedStmt *parsetree, char *url, uint32 shar
 
 		rel = open_relation_from_parsetree(parsetree, lockmode, &is_index);
 
-		get_immutable_index_options(parsetree, &newurl, &newshards, &newTypeName, &newalias, &newuuid);
+		get_immutable_index_options(parsetree, &newurl, &newSearchUrl, &newshards, &newTypeName, &newalias, &newuuid);
 
 		if (strcmp(url, newurl) != 0) {
 			if (!session_auth_is_superuser)
 				elog(ERROR, "You must be a superuser to change the 'url' parameter");
 		}
 
+		if (strcmp(searchUrl, newSearchUrl) != 0) {
+			if (!session_auth_is_superuser)
+				elog(ERROR, "You must be a superuser to change the 'search_url' parameter");
+		}
+
 		if (strcmp(typeName, newTypeName) != 0) {
 			elog(ERROR, "The 'type_name' index properly can only be set during CREATE INDEX");
 		}
@@ -2042,25 +2818,27 @@ static Relation open_relation_from_parsetree(PlannedStmt *parsetree, LOCKMODE lo
 	return NULL;
 }
 
-static void get_immutable_index_options(PlannedStmt *parsetree, char **url, uint32 *shards, char **typeName, char **alias, char **uuid) {
+static void get_immutable_index_options(PlannedStmt *parsetree, char **url, char **searchUrl, uint32 *shards, char **typeName, char **alias, char **uuid) {
 	LOCKMODE lockmode = NoLock;
 	Relation rel;
 	bool     is_index;
 
-	*url      = NULL;
-	*shards   = 0;
-	*typeName = NULL;
-	*alias    = NULL;
-	*uuid     = NULL;
+	*url       = NULL;
+	*searchUrl = NULL;
+	*shards    = 0;
+	*typeName  = NULL;
+	*alias     = NULL;
+	*uuid      = NULL;
 
 	rel = open_relation_from_parsetree(parsetree, AccessShareLock, &is_index);
 	if (RelationIsValid(rel)) {
 		if (is_index) {
-			*url      = ZDBIndexOptionsGetUrl(rel);
-			*shards   = ZDBIndexOptionsGetNumberOfShards(rel);
-			*typeName = pstrdup(ZDBIndexOptionsGetTypeName(rel));
-			*alias    = ZDBIndexOptionsGetAlias(rel) != NULL ? pstrdup(ZDBIndexOptionsGetAlias(rel)) : NULL;
-			*uuid     = pstrdup(ZDBIndexOptionsGetIndexName(rel));
+			*url       = ZDBIndexOptionsGetUrls(rel);
+			*searchUrl = pstrdup(ZDBIndexOptionsGetSearchUrls(rel) != NULL ? ZDBIndexOptionsGetSearchUrls(rel) : "");
+			*shards    = ZDBIndexOptionsGetNumberOfShards(rel);
+			*typeName  = pstrdup(ZDBIndexOptionsGetTypeName(rel));
+			*alias     = ZDBIndexOptionsGetAlias(rel) != NULL ? pstrdup(ZDBIndexOptionsGetAlias(rel)) : NULL;
+			*uuid      = pstrdup(ZDBIndexOptionsGetIndexName(rel));
 		}
 
 		relation_close(rel, lockmode);
@@ -31,6 +31,7 @@ typedef struct ZDBIndexChangeContext {
 
 ZDBIndexChangeContext *checkout_insert_context(Relation indexRelation);
 void finish_inserts(bool is_commit);
+char *lookup_routing_for_ctid(Relation indexRel, ItemPointer ctid);
 Datum collect_used_xids(MemoryContext memoryContext);
 
 #endif /* __ZDB_ZDBAM_H__ */
@@ -17,26 +17,43 @@
 #include "zombodb.h"
 
 #include "elasticsearch/elasticsearch.h"
+#include "rest/rest.h"
+#include "json/json.h"
+#include "json/json_support.h"
 #include "indexam/zdbam.h"
 
+#include "access/heapam.h"
+#include "access/htup_details.h"
 #include "access/xact.h"
+#include "catalog/index.h"
 #include "nodes/relation.h"
 #include "parser/parsetree.h"
 #include "utils/lsyscache.h"
 #include "utils/selfuncs.h"
 #include "utils/snapmgr.h"
+#include "utils/timestamp.h"
 
 PG_FUNCTION_INFO_V1(zdb_index_name);
 PG_FUNCTION_INFO_V1(zdb_index_url);
+PG_FUNCTION_INFO_V1(zdb_cloud_id_url);
 PG_FUNCTION_INFO_V1(zdb_index_type_name);
 PG_FUNCTION_INFO_V1(zdb_request);
+PG_FUNCTION_INFO_V1(zdb_refresh);
+PG_FUNCTION_INFO_V1(zdb_sync_mapping);
+PG_FUNCTION_INFO_V1(zdb_bulk_stats);
+PG_FUNCTION_INFO_V1(zdb_internal_active_builds);
+PG_FUNCTION_INFO_V1(zdb_validate_connection);
 PG_FUNCTION_INFO_V1(zdb_restrict);
 PG_FUNCTION_INFO_V1(zdb_query_srf);
 PG_FUNCTION_INFO_V1(zdb_query_tids);
 PG_FUNCTION_INFO_V1(zdb_profile_query);
+PG_FUNCTION_INFO_V1(zdb_term_vectors);
+PG_FUNCTION_INFO_V1(zdb_explain);
+PG_FUNCTION_INFO_V1(zdb_get_document);
 PG_FUNCTION_INFO_V1(zdb_to_query_dsl);
 PG_FUNCTION_INFO_V1(zdb_json_build_object_wrapper);
 PG_FUNCTION_INFO_V1(zdb_internal_visibility_clause);
+PG_FUNCTION_INFO_V1(zdb_internal_compare_node_versions);
 
 #define zdb_array_to_json(array) DirectFunctionCall1(array_to_json, array)
 
@@ -58,12 +75,18 @@ Datum zdb_index_url(PG_FUNCTION_ARGS) {
 	char     *url;
 
 	indexRel = zdb_open_index(indexRelId, AccessShareLock);
-	url      = pstrdup(ZDBIndexOptionsGetUrl(indexRel));
+	url      = pstrdup(ZDBIndexOptionsGetUrls(indexRel));
 	relation_close(indexRel, AccessShareLock);
 
 	PG_RETURN_TEXT_P(CStringGetTextDatum(url));
 }
 
+Datum zdb_cloud_id_url(PG_FUNCTION_ARGS) {
+	char *cloudId = GET_STR(PG_GETARG_TEXT_P(0));
+
+	PG_RETURN_TEXT_P(CStringGetTextDatum(cloud_id_to_url(cloudId)));
+}
+
 Datum zdb_index_type_name(PG_FUNCTION_ARGS) {
 	Oid      indexRelId = PG_GETARG_OID(0);
 	Relation indexRel;
@@ -108,6 +131,240 @@ Datum zdb_request(PG_FUNCTION_ARGS) {
 	PG_RETURN_TEXT_P(CStringGetTextDatum(response));
 }
 
+Datum zdb_refresh(PG_FUNCTION_ARGS) {
+	Oid      indexRelId = PG_GETARG_OID(0);
+	Relation indexRel;
+	bool     success;
+
+	indexRel = zdb_open_index(indexRelId, AccessShareLock);
+	success  = ElasticsearchRefreshIndex(indexRel);
+	relation_close(indexRel, AccessShareLock);
+
+	PG_RETURN_BOOL(success);
+}
+
+/*
+ * Send the index's current mapping to Elasticsearch, which adds any fields it doesn't already have
+ */
+Datum zdb_sync_mapping(PG_FUNCTION_ARGS) {
+	Oid       indexRelId = PG_GETARG_OID(0);
+	Relation  heapRel;
+	Relation  indexRel;
+	TupleDesc tupdesc;
+
+	heapRel  = heap_open(IndexGetRelation(indexRelId, false), AccessShareLock);
+	indexRel = zdb_open_index(indexRelId, AccessShareLock);
+
+	tupdesc = extract_tuple_desc_from_index_expressions(BuildIndexInfo(indexRel));
+	if (tupdesc == NULL)
+		elog(ERROR, "unable to determine the row type of index '%s'", RelationGetRelationName(indexRel));
+
+	ElasticsearchPutMapping(heapRel, indexRel, tupdesc);
+	ReleaseTupleDesc(tupdesc);
+
+	relation_close(indexRel, AccessShareLock);
+	heap_close(heapRel, AccessShareLock);
+
+	PG_RETURN_VOID();
+}
+
+Datum zdb_bulk_stats(PG_FUNCTION_ARGS) {
+	FuncCallContext *funcctx;
+	List            *entries;
+
+	if (SRF_IS_FIRSTCALL()) {
+		MemoryContext          oldContext;
+		HASH_SEQ_STATUS        seq;
+		ElasticsearchBulkStats *stats;
+		TupleDesc              tupdesc;
+
+		funcctx    = SRF_FIRSTCALL_INIT();
+		oldContext = MemoryContextSwitchTo(funcctx->multi_call_memory_ctx);
+
+		if (get_call_result_type(fcinfo, NULL, &tupdesc) != TYPEFUNC_COMPOSITE)
+			elog(ERROR, "return type must be a row type");
+		funcctx->tuple_desc = BlessTupleDesc(tupdesc);
+
+		/* copy the entries so they can't change underneath us while we return them */
+		entries = NIL;
+		if (PG_GETARG_BOOL(0)) {
+			entries = ElasticsearchGetSharedBulkStats();
+		} else {
+			hash_seq_init(&seq, ElasticsearchGetBulkStats());
+			while ((stats = hash_seq_search(&seq)) != NULL) {
+				ElasticsearchBulkStats *copy = palloc(sizeof(ElasticsearchBulkStats));
+
+				memcpy(copy, stats, sizeof(ElasticsearchBulkStats));
+				entries = lappend(entries, copy);
+			}
+		}
+
+		funcctx->user_fctx = entries;
+		funcctx->max_calls = list_length(entries);
+		MemoryContextSwitchTo(oldContext);
+	}
+
+	funcctx = SRF_PERCALL_SETUP();
+	entries = (List *) funcctx->user_fctx;
+
+	if (funcctx->call_cntr < funcctx->max_calls) {
+		ElasticsearchBulkStats *stats = list_nth(entries, (int) funcctx->call_cntr);
+		Datum                  values[10];
+		bool                   nulls[10] = {false, false, false, false, false, false, false, false, false, false};
+		double                 percentiles[3] = {50, 95, 99};
+		int                    i;
+
+		values[0] = ObjectIdGetDatum(stats->indexRelid);
+		values[1] = Int64GetDatum((int64) stats->requests);
+		values[2] = Int64GetDatum((int64) stats->documents);
+		values[3] = Int64GetDatum((int64) stats->versionConflicts);
+
+		for (i = 0; i < 3; i++) {
+			int64 ms = latency_histogram_percentile(&stats->latencies, percentiles[i]);
+
+			values[4 + i] = Int64GetDatum(ms);
+			nulls[4 + i]  = ms < 0;
+		}
+
+		values[7] = Float8GetDatum(stats->requests == 0 ? 0 : (double) stats->queueDepth / stats->requests);
+		values[8] = Int64GetDatum((int64) stats->latencies.sum);
+		values[9] = Int64GetDatum((int64) latency_histogram_count(&stats->latencies));
+
+		SRF_RETURN_NEXT(funcctx, HeapTupleGetDatum(heap_form_tuple(funcctx->tuple_desc, values, nulls)));
+	}
+
+	SRF_RETURN_DONE(funcctx);
+}
+
+Datum zdb_internal_active_builds(PG_FUNCTION_ARGS) {
+	FuncCallContext *funcctx;
+	List            *builds;
+
+	if (SRF_IS_FIRSTCALL()) {
+		MemoryContext oldContext;
+		TupleDesc     tupdesc;
+
+		funcctx    = SRF_FIRSTCALL_INIT();
+		oldContext = MemoryContextSwitchTo(funcctx->multi_call_memory_ctx);
+
+		if (get_call_result_type(fcinfo, NULL, &tupdesc) != TYPEFUNC_COMPOSITE)
+			elog(ERROR, "return type must be a row type");
+		funcctx->tuple_desc = BlessTupleDesc(tupdesc);
+
+		/* a copy, so the builds can keep going while we return them */
+		builds = ElasticsearchGetActiveBuilds();
+
+		funcctx->user_fctx = builds;
+		funcctx->max_calls = list_length(builds);
+		MemoryContextSwitchTo(oldContext);
+	}
+
+	funcctx = SRF_PERCALL_SETUP();
+	builds  = (List *) funcctx->user_fctx;
+
+	if (funcctx->call_cntr < funcctx->max_calls) {
+		ElasticsearchActiveBuild *build = list_nth(builds, (int) funcctx->call_cntr);
+		Datum                    values[7];
+		bool                     nulls[7] = {false, false, false, false, false, false, false};
+
+		values[0] = Int32GetDatum(build->pid);
+		values[1] = ObjectIdGetDatum(build->indexRelid);
+		values[2] = TimestampTzGetDatum(build->started);
+		values[3] = Int64GetDatum((int64) build->rows);
+		values[4] = Int64GetDatum((int64) build->requests);
+		values[5] = Int32GetDatum(build->inflight);
+		values[6] = Int32GetDatum(build->bulkConcurrency);
+
+		SRF_RETURN_NEXT(funcctx, HeapTupleGetDatum(heap_form_tuple(funcctx->tuple_desc, values, nulls)));
+	}
+
+	SRF_RETURN_DONE(funcctx);
+}
+
+/*
+ * The string member of a json object, or NULL if it's missing or isn't a string
+ */
+static const char *json_member_string(struct json_value_s *object, char *key) {
+	struct json_value_s *value;
+
+	if (object == NULL || object->type != json_type_object)
+		return NULL;
+
+	value = get_json_object_object(object, key, true);
+	return value != NULL && value->type == json_type_string ? ((struct json_string_s *) value->payload)->string : NULL;
+}
+
+/*
+ * Why a "GET /" didn't get a cluster's answer:  the http response code and, if it was an Elasticsearch
+ * error, its type and reason.  Never the response body itself, as whatever the url pointed at could
+ * have answered
+ */
+static char *describe_connection_failure(int64 response_code, StringInfo response) {
+	struct json_value_s *json;
+	const char          *type;
+	const char          *reason;
+
+	if (!is_json(response->data))
+		return psprintf("http response code %ld", response_code);
+
+	json   = parse_json_object(response, CurrentMemoryContext);
+	type   = json_member_string(get_json_object_object(json, "error", true), "type");
+	reason = json_member_string(get_json_object_object(json, "error", true), "reason");
+
+	if (type == NULL)
+		return psprintf("http response code %ld", response_code);
+
+	return psprintf("http response code %ld: %s: %s", response_code, type, reason ? reason : "unknown");
+}
+
+/*
+ * Make a "GET /" request of the Elasticsearch cluster at the specified url, without needing an index,
+ * to say whether we can reach it and, if so, what it is
+ */
+Datum zdb_validate_connection(PG_FUNCTION_ARGS) {
+	char       *url     = GET_STR(PG_GETARG_TEXT_P(0));
+	StringInfo request  = makeStringInfo();
+	StringInfo response;
+	int64      response_code;
+	TupleDesc  tupdesc;
+	Datum      values[4];
+	bool       nulls[4] = {false, true, true, true};
+
+	if (get_call_result_type(fcinfo, NULL, &tupdesc) != TYPEFUNC_COMPOSITE)
+		elog(ERROR, "return type must be a row type");
+	tupdesc = BlessTupleDesc(tupdesc);
+
+	appendStringInfo(request, "%s%s", url, url[0] != '\0' && url[strlen(url) - 1] == '/' ? "" : "/");
+	response = rest_call_unchecked("GET", request, NULL, 0, &response_code);
+
+	if (response_code == 200 && is_json(response->data)) {
+		void       *json    = parse_json_object(response, CurrentMemoryContext);
+		const char *name    = json_member_string(json, "cluster_name");
+		const char *version = json_member_string(get_json_object_object(json, "version", true), "number");
+
+		values[0] = BoolGetDatum(true);
+		if (name != NULL) {
+			values[1] = CStringGetTextDatum(name);
+			nulls[1]  = false;
+		}
+		if (version != NULL) {
+			values[2] = CStringGetTextDatum(version);
+			nulls[2]  = false;
+		}
+	} else {
+		/* curl's own error when we couldn't make the request at all, otherwise what the server said */
+		values[0] = BoolGetDatum(false);
+		values[3] = CStringGetTextDatum(response_code == 0 ? response->data :
+										describe_connection_failure(response_code, response));
+		nulls[3]  = false;
+	}
+
+	freeStringInfo(response);
+	freeStringInfo(request);
+
+	PG_RETURN_DATUM(HeapTupleGetDatum(heap_form_tuple(tupdesc, values, nulls)));
+}
+
 Datum zdb_restrict(PG_FUNCTION_ARGS) {
 	PlannerInfo      *root         = (PlannerInfo *) PG_GETARG_POINTER(0);
 //	Oid              operator    = PG_GETARG_OID(1);
@@ -285,6 +542,53 @@ Datum zdb_profile_query(PG_FUNCTION_ARGS) {
 	PG_RETURN_TEXT_P(CStringGetTextDatum(response));
 }
 
+Datum zdb_term_vectors(PG_FUNCTION_ARGS) {
+	Oid         indexRelOid = PG_GETARG_OID(0);
+	ItemPointer ctid        = PG_GETARG_ITEMPOINTER(1);
+	char        *field      = GET_STR(PG_GETARG_TEXT_P(2));
+	Relation    indexRel;
+	char        *termVectors;
+
+	indexRel    = zdb_open_index(indexRelOid, AccessShareLock);
+	termVectors = ElasticsearchTermVectors(indexRel, ctid, field);
+	relation_close(indexRel, AccessShareLock);
+
+	if (termVectors == NULL)
+		PG_RETURN_NULL();
+
+	PG_RETURN_DATUM(DirectFunctionCall1(jsonb_in, CStringGetDatum(termVectors)));
+}
+
+Datum zdb_explain(PG_FUNCTION_ARGS) {
+	Oid          indexRelOid = PG_GETARG_OID(0);
+	ZDBQueryType *query      = (ZDBQueryType *) PG_GETARG_VARLENA_P(1);
+	ItemPointer  ctid        = PG_GETARG_ITEMPOINTER(2);
+	Relation     indexRel;
+	char         *explanation;
+
+	indexRel    = zdb_open_index(indexRelOid, AccessShareLock);
+	explanation = ElasticsearchExplain(indexRel, query, ctid);
+	relation_close(indexRel, AccessShareLock);
+
+	PG_RETURN_DATUM(DirectFunctionCall1(jsonb_in, CStringGetDatum(explanation)));
+}
+
+Datum zdb_get_document(PG_FUNCTION_ARGS) {
+	Oid         indexRelOid = PG_GETARG_OID(0);
+	ItemPointer ctid        = PG_GETARG_ITEMPOINTER(1);
+	Relation    indexRel;
+	char        *source;
+
+	indexRel = zdb_open_index(indexRelOid, AccessShareLock);
+	source   = ElasticsearchGetDocument(indexRel, ctid);
+	relation_close(indexRel, AccessShareLock);
+
+	if (source == NULL)
+		PG_RETURN_NULL();
+
+	PG_RETURN_DATUM(DirectFunctionCall1(jsonb_in, CStringGetDatum(source)));
+}
+
 Datum zdb_to_query_dsl(PG_FUNCTION_ARGS) {
 	ZDBQueryType *query = (ZDBQueryType *) PG_GETARG_VARLENA_P(0);
 	char         *dsl   = zdbquery_get_query(query);
@@ -554,3 +858,17 @@ Datum zdb_internal_visibility_clause(PG_FUNCTION_ARGS) {
 
     PG_RETURN_POINTER(MakeZDBQuery(query->data));
 }
+
+/*
+ * zdb.check_node_versions' comparison of a "GET _nodes" response, by itself, so that it can be tested
+ * without a cluster whose nodes run different versions
+ */
+Datum zdb_internal_compare_node_versions(PG_FUNCTION_ARGS) {
+	char       *url     = GET_STR(PG_GETARG_TEXT_P(0));
+	StringInfo response = makeStringInfo();
+
+	appendStringInfoString(response, GET_STR(PG_GETARG_TEXT_P(1)));
+	ElasticsearchCompareNodeVersions(url, response);
+
+	PG_RETURN_VOID();
+}
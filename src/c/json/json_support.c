@@ -89,6 +89,12 @@ void *get_value_from_json_object_iterator(JsonObjectKeyIterator itr) {
 	return ((struct json_object_element_s *) itr)->value->payload;
 }
 
+const char *get_string_value_from_json_object_iterator(JsonObjectKeyIterator itr) {
+	struct json_value_s *value = ((struct json_object_element_s *) itr)->value;
+
+	return value->type == json_type_string ? ((struct json_string_s *) value->payload)->string : NULL;
+}
+
 JsonObjectKeyIterator get_next_from_json_object_iterator(JsonObjectKeyIterator itr) {
 	return ((struct json_object_element_s *) itr)->next;
 }
@@ -33,6 +33,7 @@ void *parse_json_object_from_string(char *jsonString, MemoryContext memcxt);
 JsonObjectKeyIterator get_json_object_key_iterator(void *object);
 const char *get_key_from_json_object_iterator(JsonObjectKeyIterator itr);
 void *get_value_from_json_object_iterator(JsonObjectKeyIterator itr);
+const char *get_string_value_from_json_object_iterator(JsonObjectKeyIterator itr);
 JsonObjectKeyIterator get_next_from_json_object_iterator(JsonObjectKeyIterator itr);
 
 void *get_json_object_object(void *object, char *key, bool missingOk);
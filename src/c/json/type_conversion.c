@@ -15,11 +15,26 @@
  */
 
 #include "type_conversion.h"
+#include "indexam/zdb_index_options.h"
 #include "access/htup_details.h"
 #include "catalog/pg_type.h"
 #include "executor/spi.h"
 #include "utils/builtins.h"
+#include "utils/date.h"
+#include "utils/array.h"
 #include "utils/json.h"
+#include "utils/lsyscache.h"
+#include "utils/numeric.h"
+#include "utils/timestamp.h"
+#include "utils/utils.h"
+
+#include <float.h>
+#include <math.h>
+
+/* not every Postgres version we support defines this one */
+#ifndef FLOAT8ARRAYOID
+#define FLOAT8ARRAYOID 1022
+#endif
 
 /* copied from json_pg11.c */
 typedef enum                    /* type categories for datum_to_json */
@@ -68,15 +83,125 @@ static Oid lookup_json_converter(Oid typeoid) {
     return funcoid;
 }
 
-JsonConversion **build_json_conversions(TupleDesc tupdesc) {
-    JsonConversion **conversions = palloc(sizeof(JsonConversion *) * tupdesc->natts);
-    int            i;
+static bool is_infinite_datetime(Oid typeoid, Datum val) {
+    switch (typeoid) {
+        case DATEOID:
+            return DATE_NOT_FINITE(DatumGetDateADT(val));
+        case TIMESTAMPOID:
+        case TIMESTAMPTZOID:
+            return TIMESTAMP_NOT_FINITE(DatumGetTimestamp(val));
+        default:
+            return false;
+    }
+}
+
+static bool is_nonfinite_float(Oid typeoid, Datum val) {
+    switch (typeoid) {
+        case FLOAT4OID:
+            return isinf(DatumGetFloat4(val)) || isnan(DatumGetFloat4(val));
+        case FLOAT8OID:
+            return isinf(DatumGetFloat8(val)) || isnan(DatumGetFloat8(val));
+        default:
+            return false;
+    }
+}
+
+/*
+ * Elasticsearch rejects NaN and +/- Infinity, so do what the index's 'nonfinite_floats' option says with them instead
+ */
+static void append_nonfinite_float(StringInfo json, Oid typeoid, double value, char *attname, NonFiniteFloats nonfinite) {
+    if (nonfinite == NONFINITE_FLOATS_ERROR)
+        ereport(ERROR,
+                (errcode(ERRCODE_NUMERIC_VALUE_OUT_OF_RANGE),
+                        errmsg("column \"%s\" contains %s, which Elasticsearch can't index", attname,
+                               isnan(value) ? "NaN" : value < 0 ? "-Infinity" : "Infinity"),
+                        errhint("Set the index's 'nonfinite_floats' option to 'null' or 'clamp' to index it anyway")));
+
+    if (nonfinite == NONFINITE_FLOATS_CLAMP && !isnan(value)) {
+        if (typeoid == FLOAT4OID)
+            appendStringInfo(json, "%.9g", value < 0 ? -FLT_MAX : FLT_MAX);
+        else
+            appendStringInfo(json, "%.17g", value < 0 ? -DBL_MAX : DBL_MAX);
+    } else {
+        appendStringInfoString(json, "null");
+    }
+}
+
+/*
+ * If this is a float4[] or float8[] with any non-finite values, convert it ourselves
+ */
+static bool append_float_array(StringInfo json, Oid typeoid, Datum val, char *attname, NonFiniteFloats nonfinite) {
+    ArrayType *array;
+    Oid       elemtype = typeoid == FLOAT4ARRAYOID ? FLOAT4OID : FLOAT8OID;
+    int16     typlen;
+    bool      typbyval;
+    char      typalign;
+    Datum     *elems;
+    bool      *nulls;
+    int       nelems;
+    int       i;
+
+    if (typeoid != FLOAT4ARRAYOID && typeoid != FLOAT8ARRAYOID)
+        return false;
+
+    array = DatumGetArrayTypeP(val);
+    get_typlenbyvalalign(elemtype, &typlen, &typbyval, &typalign);
+    deconstruct_array(array, elemtype, typlen, typbyval, typalign, &elems, &nulls, &nelems);
+
+    for (i = 0; i < nelems; i++) {
+        if (!nulls[i] && is_nonfinite_float(elemtype, elems[i]))
+            break;
+    }
+
+    if (i == nelems)
+        return false;
+
+    /* Elasticsearch flattens arrays anyways, so a multi-dimensional one becomes a flat list */
+    appendStringInfoChar(json, '[');
+    for (i = 0; i < nelems; i++) {
+        if (i > 0)
+            appendStringInfoChar(json, ',');
+
+        if (nulls[i]) {
+            appendStringInfoString(json, "null");
+        } else {
+            double value = elemtype == FLOAT4OID ? DatumGetFloat4(elems[i]) : DatumGetFloat8(elems[i]);
+
+            if (is_nonfinite_float(elemtype, elems[i]))
+                append_nonfinite_float(json, elemtype, value, attname, nonfinite);
+            else
+                appendStringInfoString(json, DatumGetCString(DirectFunctionCall1(
+                        elemtype == FLOAT4OID ? float4out : float8out, elems[i])));
+        }
+    }
+    appendStringInfoChar(json, ']');
+
+    return true;
+}
+
+JsonConversion **build_json_conversions(Relation indexRel, TupleDesc tupdesc) {
+    JsonConversion  **conversions = palloc(sizeof(JsonConversion *) * tupdesc->natts);
+    char            *option       = ZDBIndexOptionsGetNonFiniteFloats(indexRel);
+    NonFiniteFloats nonfinite     = strcmp("clamp", option) == 0 ? NONFINITE_FLOATS_CLAMP :
+                                    strcmp("error", option) == 0 ? NONFINITE_FLOATS_ERROR : NONFINITE_FLOATS_NULL;
+    int             i;
 
     for (i = 0; i < tupdesc->natts; i++) {
         Form_pg_attribute attr = TupleDescAttr(tupdesc, i);
 
-        conversions[i] = palloc(sizeof(JsonConversion));
+        conversions[i] = palloc0(sizeof(JsonConversion));
         conversions[i]->funcoid = lookup_json_converter(attr->atttypid);
+        conversions[i]->skip    = !column_is_indexed(indexRel, NameStr(attr->attname));
+        conversions[i]->fieldName = es_field_name(indexRel, NameStr(attr->attname));
+        conversions[i]->nonfinite = nonfinite;
+
+        if (conversions[i]->funcoid == InvalidOid && !attr->attisdropped) {
+            /* categorize the type once, here, rather than for every row we convert */
+            JsonTypeCategory tcategory;
+
+            json_categorize_type(attr->atttypid, &tcategory, &conversions[i]->outfuncoid);
+            conversions[i]->tcategory = (int) tcategory;
+        }
     }
 
     return conversions;
@@ -105,28 +230,36 @@ void zdb_row_to_json(StringInfo json, Datum row, TupleDesc tupdesc, JsonConversi
         Datum             val;
         bool isnull;
         char              *attname;
-        JsonTypeCategory  tcategory;
-        Oid               outfuncoid;
         Form_pg_attribute att = TupleDescAttr(tupdesc, i);
 
-        if (att->attisdropped)
+        if (att->attisdropped || conversions[i]->skip)
             continue;
 
         if (json->len > 1)
             appendStringInfoChar(json, ',');
 
         attname = NameStr(att->attname);
-        escape_json(json, attname);
+        escape_json(json, conversions[i]->fieldName);
         appendStringInfoChar(json, ':');
 
         val = heap_getattr(tuple, i + 1, tupdesc, &isnull);
 
         if (isnull) {
             appendStringInfoString(json, "null");
+        } else if (att->atttypid == NUMERICOID && numeric_is_nan(DatumGetNumeric(val))) {
+            /* Elasticsearch has no way to represent NaN */
+            appendStringInfoString(json, "null");
+        } else if (is_infinite_datetime(att->atttypid, val)) {
+            /* ...nor +/- infinity dates and timestamps */
+            appendStringInfoString(json, "null");
+        } else if (is_nonfinite_float(att->atttypid, val)) {
+            append_nonfinite_float(json, att->atttypid,
+                                   att->atttypid == FLOAT4OID ? DatumGetFloat4(val) : DatumGetFloat8(val), attname,
+                                   conversions[i]->nonfinite);
         } else if (conversions[i]->funcoid == InvalidOid) {
-            json_categorize_type(att->atttypid,
-                                 &tcategory, &outfuncoid);
-            datum_to_json(val, isnull, json, tcategory, outfuncoid, false);
+            if (!append_float_array(json, att->atttypid, val, attname, conversions[i]->nonfinite))
+                datum_to_json(val, isnull, json, (JsonTypeCategory) conversions[i]->tcategory,
+                              conversions[i]->outfuncoid, false);
         } else {
             Datum conv = OidFunctionCall2(conversions[i]->funcoid, val, Int32GetDatum(att->atttypmod));
             appendStringInfoString(json, TextDatumGetCString(conv));
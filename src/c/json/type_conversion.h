@@ -20,12 +20,25 @@
 #include "postgres.h"
 #include "access/tupdesc.h"
 #include "lib/stringinfo.h"
+#include "utils/rel.h"
+
+/* what zdb_row_to_json() does with NaN and +/- Infinity float4/float8 values.  See the 'nonfinite_floats' index option */
+typedef enum NonFiniteFloats {
+    NONFINITE_FLOATS_NULL,  /* index them as null */
+    NONFINITE_FLOATS_CLAMP, /* index +/- Infinity as the type's largest finite value, and NaN as null */
+    NONFINITE_FLOATS_ERROR  /* raise an ERROR */
+} NonFiniteFloats;
 
 typedef struct JsonConversion {
-    Oid funcoid;
+    Oid  funcoid;
+    bool skip;      /* excluded by the index's include_columns/exclude_columns options */
+    int  tcategory; /* when funcoid is InvalidOid, how Postgres' own json support categorizes the type... */
+    Oid  outfuncoid;    /* ...and the type's output function */
+    char *fieldName;    /* the column's Elasticsearch field name, possibly in the TupleDesc these were built from.  See 'dot_replacement' */
+    NonFiniteFloats nonfinite; /* the index's 'nonfinite_floats' option */
 } JsonConversion;
 
-JsonConversion **build_json_conversions(TupleDesc tupdesc);
+JsonConversion **build_json_conversions(Relation indexRel, TupleDesc tupdesc);
 
 void zdb_row_to_json(StringInfo json, Datum row, TupleDesc tupdesc, JsonConversion **conversions);
 
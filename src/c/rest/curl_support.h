@@ -19,6 +19,7 @@
 
 #include "postgres.h"
 #include "lib/stringinfo.h"
+#include "datatype/timestamp.h"
 #include "nodes/pg_list.h"
 
 #include <curl/curl.h>
@@ -32,6 +33,19 @@ typedef struct PostDataEntry {
 	char       *compressed_data;
 } PostDataEntry;
 
+/* a bounded, log-linear histogram of request latencies, in milliseconds.  See rest.c:latency_bucket() */
+#define LATENCY_HISTOGRAM_BUCKETS 272
+
+typedef struct LatencyHistogram {
+	uint64 counts[LATENCY_HISTOGRAM_BUCKETS];
+	uint64 sum;    /* of every latency recorded, so it's exact even though the buckets aren't */
+} LatencyHistogram;
+
+typedef struct DeadLetter {
+	char *id;        /* the _id of the document Elasticsearch rejected */
+	char *reason;
+} DeadLetter;
+
 typedef struct MultiRestState {
 	int               nhandles;
 	CURL              *handles[MAX_CURL_HANDLES];
@@ -45,6 +59,23 @@ typedef struct MultiRestState {
 	int   available;
 
 	StringInfo *pool;
+
+	bool skip_errors;       /* should per-document _bulk errors be collected rather than raised? */
+	List *dead_letters;     /* of type DeadLetter, allocated in TopTransactionContext */
+
+	uint64 nconflicts;      /* how many version conflicts has Elasticsearch reported? */
+	int    nrejections;     /* how deep we are in sending documents Elasticsearch rejected as overloaded again */
+
+	LatencyHistogram latencies;  /* how long did each of our requests take? */
+
+	int  compression_level;  /* of the most recent request, for when we need to resend part of it */
+	bool payload_too_large;  /* has Elasticsearch rejected a request as too large since this was last cleared? */
+
+	int limit;              /* how many handles we use at once, lowered while Elasticsearch rejects requests with a 429 */
+	int naccepted;          /* requests accepted since limit last changed */
+
+	double      retry_tokens;     /* what's left of the zdb.bulk_retry_budget */
+	TimestampTz retry_refilled;   /* when retry_tokens was last topped up */
 } MultiRestState;
 
 extern CURL *GLOBAL_CURL_INSTANCE;
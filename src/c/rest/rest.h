@@ -20,11 +20,21 @@
 #include "curl_support.h"
 
 StringInfo rest_call(char *method, StringInfo url, StringInfo postData, int compressionLevel);
+StringInfo rest_call_unchecked(char *method, StringInfo url, StringInfo postData, int compressionLevel, int64 *response_code);
 
-MultiRestState *rest_multi_init(int nhandles, bool ignore_version_conflicts);
+MultiRestState *rest_multi_init(int nhandles, bool ignore_version_conflicts, bool skip_errors);
 int rest_multi_perform(MultiRestState *state);
 void rest_multi_call(MultiRestState *state, char *method, StringInfo url, PostDataEntry *postData, int compressionLevel);
+void rest_multi_call_sync(MultiRestState *state, StringInfo url, PostDataEntry *postData, int compressionLevel, bool ignore_version_conflicts);
 void rest_multi_wait_for_all_done(MultiRestState *state);
 void rest_multi_partial_cleanup(MultiRestState *state, bool finalize, bool fast);
 
+char *rest_choose_url(char *urls);
+char *rest_url_encode(const char *str);
+
+void latency_histogram_add(LatencyHistogram *histogram, uint64 ms);
+void latency_histogram_merge(LatencyHistogram *into, LatencyHistogram *from);
+uint64 latency_histogram_count(LatencyHistogram *histogram);
+int64 latency_histogram_percentile(LatencyHistogram *histogram, double percentile);
+
 #endif /* __ZDB_REST_H__ */
@@ -27,14 +27,18 @@
 #include "catalog/objectaccess.h"
 #include "catalog/pg_trigger.h"
 #include "commands/trigger.h"
+#include "common/base64.h"
 #include "executor/spi.h"
 #include "nodes/makefuncs.h"
 #include "nodes/nodeFuncs.h"
+#include "parser/parse_func.h"
 #include "parser/parsetree.h"
 #include "utils/lsyscache.h"
+#include "utils/regproc.h"
 #include "utils/ruleutils.h"
 #include "utils/syscache.h"
 #include "utils/typcache.h"
+#include "utils/varlena.h"
 
 typedef struct {
 	TransactionId last_xid;
@@ -501,6 +505,54 @@ List *lookup_zdb_indexes_in_namespace(Oid namespaceOid) {
 	return oids;
 }
 
+/*
+ * Find another ZomboDB index, in this database, that uses the same Elasticsearch index as 'indexRel'.
+ * Returns its name, or NULL if there isn't one
+ */
+char *lookup_zdb_index_sharing_uuid(Relation indexRel) {
+	MemoryContext current_context = CurrentMemoryContext;
+	char          *indexName      = ZDBIndexOptionsGetIndexName(indexRel);
+	char          *url            = ZDBIndexOptionsGetUrls(indexRel);
+	char          *other          = NULL;
+	int           rc;
+	uint64        cnt;
+
+	if (indexName == NULL)
+		return NULL;
+
+	SPI_connect();
+
+	rc = SPI_execute(psprintf(
+			"select oid from pg_class where oid <> %u and relam = (select oid from pg_am where amname = 'zombodb')",
+			RelationGetRelid(indexRel)), true, INT_MAX);
+	if (rc != SPI_OK_SELECT)
+		elog(ERROR, "Unable to lookup ZomboDB indexes");
+
+	for (cnt = 0; cnt < SPI_processed && other == NULL; cnt++) {
+		Datum    d;
+		bool     isnull;
+		Relation otherRel;
+
+		d = SPI_getbinval(SPI_tuptable->vals[cnt], SPI_tuptable->tupdesc, 1, &isnull);
+		if (isnull)
+			continue;
+
+		otherRel = RelationIdGetRelation(DatumGetObjectId(d));
+		if (otherRel == NULL)
+			continue;
+
+		if (ZDBIndexOptionsGetIndexName(otherRel) != NULL &&
+			strcmp(indexName, ZDBIndexOptionsGetIndexName(otherRel)) == 0 &&
+			strcmp(url, ZDBIndexOptionsGetUrls(otherRel)) == 0)
+			other = MemoryContextStrdup(current_context, RelationGetRelationName(otherRel));
+		RelationClose(otherRel);
+	}
+
+	SPI_finish();
+
+	return other;
+}
+
 /*
  * adapted from Postgres' commands/tablecmds.c#ATExecSetRelOptions()
  */
@@ -587,3 +639,190 @@ void set_index_option(Relation rel, char *key, char *value) {
 	 */
 	CommandCounterIncrement();
 }
+
+/*
+ * Was the named option given in the index's definition, as opposed to taking its default?
+ */
+bool index_option_is_set(Relation rel, char *key) {
+	HeapTuple tuple;
+	Datum     datum;
+	bool      isnull;
+	bool      found = false;
+
+	tuple = SearchSysCache1(RELOID, ObjectIdGetDatum(RelationGetRelid(rel)));
+	if (!HeapTupleIsValid(tuple))
+		elog(ERROR, "cache lookup failed for relation %u", RelationGetRelid(rel));
+
+	datum = SysCacheGetAttr(RELOID, tuple, Anum_pg_class_reloptions, &isnull);
+	if (!isnull) {
+		ListCell *lc;
+
+		foreach (lc, untransformRelOptions(datum)) {
+			DefElem *def = lfirst(lc);
+
+			if (strcmp(def->defname, key) == 0) {
+				found = true;
+				break;
+			}
+		}
+	}
+
+	ReleaseSysCache(tuple);
+	return found;
+}
+
+static bool column_list_contains(char *list, char *attname) {
+	List     *names;
+	ListCell *lc;
+
+	if (!SplitIdentifierString(pstrdup(list), ',', &names))
+		elog(ERROR, "invalid column list: %s", list);
+
+	foreach (lc, names) {
+		if (strcmp(lfirst(lc), attname) == 0)
+			return true;
+	}
+
+	return false;
+}
+
+/*
+ * Should the named column be part of the documents we send to Elasticsearch, according to
+ * the index's 'include_columns' and 'exclude_columns' options?
+ */
+bool column_is_indexed(Relation indexRel, char *attname) {
+	char *include = ZDBIndexOptionsGetIncludeColumns(indexRel);
+	char *exclude = ZDBIndexOptionsGetExcludeColumns(indexRel);
+
+	if (include != NULL)
+		return column_list_contains(include, attname);
+	else if (exclude != NULL)
+		return !column_list_contains(exclude, attname);
+
+	return true;
+}
+
+/*
+ * Find the function named by an index's 'transform_function' option, which must take a single
+ * jsonb argument and return jsonb
+ */
+Oid lookup_transform_function(char *name) {
+	Oid argtypes[1] = {JSONBOID};
+	Oid funcoid;
+
+	funcoid = LookupFuncName(stringToQualifiedNameList(name), 1, argtypes, false);
+	if (get_func_rettype(funcoid) != JSONBOID)
+		ereport(ERROR,
+				(errcode(ERRCODE_INVALID_FUNCTION_DEFINITION),
+						errmsg("'transform_function' index option function %s(jsonb) must return jsonb", name)));
+
+	return funcoid;
+}
+
+/*
+ * The name of the Elasticsearch field for the named column.  That's the column's own name, unless
+ * it has dots, which Elasticsearch would expand into nested objects, and the index's 'dot_replacement'
+ * option says what to use instead
+ */
+char *es_field_name(Relation indexRel, char *attname) {
+	char       *replacement = ZDBIndexOptionsGetDotReplacement(indexRel);
+	StringInfo name;
+	char       *c;
+
+	if (replacement == NULL || strchr(attname, '.') == NULL)
+		return attname;
+
+	name = makeStringInfo();
+	for (c = attname; *c; c++) {
+		if (*c == '.')
+			appendStringInfoString(name, replacement);
+		else
+			appendStringInfoChar(name, *c);
+	}
+
+	return name->data;
+}
+
+/* the cloud ids we've decoded in this backend, and the urls they decoded to */
+typedef struct CloudIdUrl {
+	char *cloudId;
+	char *url;
+} CloudIdUrl;
+
+static List *cloud_id_urls = NIL;
+
+/*
+ * Decode an Elastic Cloud id, "<name>:<base64 of host$es_uuid$kibana_uuid>", into the https url
+ * of its Elasticsearch cluster, the same way Elastic's own clients do.  Each id is only decoded
+ * once per backend.  The url never has credentials in it:  requests to it get zdb.cloud_auth from
+ * curl instead (see is_cloud_url())
+ */
+char *cloud_id_to_url(const char *cloudId) {
+	const char    *encoded = strchr(cloudId, ':') != NULL ? strchr(cloudId, ':') + 1 : cloudId;
+	int           len      = (int) strlen(encoded);
+	char          *decoded;
+	char          *host, *uuid, *port;
+	CloudIdUrl    *entry;
+	MemoryContext oldContext;
+	ListCell      *lc;
+
+	foreach (lc, cloud_id_urls) {
+		entry = lfirst(lc);
+
+		if (strcmp(entry->cloudId, cloudId) == 0)
+			return entry->url;
+	}
+
+	decoded = palloc0(pg_b64_dec_len(len) + 1);
+	if (len == 0 || pg_b64_decode(encoded, len, decoded) <= 0)
+		goto malformed;
+
+	host = decoded;
+	uuid = strchr(host, '$');
+	if (uuid == NULL || uuid == host)
+		goto malformed;
+	*uuid++ = '\0';
+
+	/* there's usually a kibana uuid after the Elasticsearch one, which we don't need */
+	if (strchr(uuid, '$') != NULL)
+		*strchr(uuid, '$') = '\0';
+	if (*uuid == '\0')
+		goto malformed;
+
+	/* the host might have its own port */
+	port = strchr(host, ':');
+	if (port != NULL)
+		*port++ = '\0';
+
+	oldContext = MemoryContextSwitchTo(TopMemoryContext);
+	entry = palloc(sizeof(CloudIdUrl));
+	entry->cloudId = pstrdup(cloudId);
+	entry->url     = psprintf("https://%s.%s:%s/", uuid, host, port != NULL && *port != '\0' ? port : "443");
+	cloud_id_urls = lappend(cloud_id_urls, entry);
+	MemoryContextSwitchTo(oldContext);
+
+	pfree(decoded);
+	return entry->url;
+
+	malformed:
+	ereport(ERROR,
+			(errcode(ERRCODE_INVALID_PARAMETER_VALUE),
+					errmsg("'cloud_id' index option is not a valid Elastic Cloud id")));
+	return NULL;    /* keep compiler quiet */
+}
+
+/*
+ * Is the url one of a decoded cloud id's, or a request against one?
+ */
+bool is_cloud_url(const char *url) {
+	ListCell *lc;
+
+	foreach (lc, cloud_id_urls) {
+		CloudIdUrl *entry = lfirst(lc);
+
+		if (strncmp(entry->url, url, strlen(entry->url)) == 0)
+			return true;
+	}
+
+	return false;
+}
@@ -65,6 +65,13 @@ TupleDesc extract_tuple_desc_from_index_expressions(IndexInfo *indexInfo);
 bool index_is_zdb_index(Relation indexRel);
 bool already_has_zdb_index(Relation heapRel, Relation indexRel);
 List *lookup_zdb_indexes_in_namespace(Oid namespaceOid);
+char *lookup_zdb_index_sharing_uuid(Relation indexRel);
 void set_index_option(Relation rel, char *key, char *value);
+bool index_option_is_set(Relation rel, char *key);
+bool column_is_indexed(Relation indexRel, char *attname);
+char *es_field_name(Relation indexRel, char *attname);
+Oid lookup_transform_function(char *name);
+char *cloud_id_to_url(const char *cloudId);
+bool is_cloud_url(const char *url);
 
 #endif /* __ZDB_UTILS_H__ */
@@ -14,6 +14,7 @@
  * limitations under the License.
  */
 #include "zombodb.h"
+#include "elasticsearch/elasticsearch.h"
 #include "highlighting/highlighting.h"
 #include "rest/curl_support.h"
 #include "scoring/scoring.h"
@@ -39,6 +40,7 @@ void _PG_init(void) {
 	json_support_init();
 	scoring_support_init();
 	highlight_support_init();
+	ElasticsearchBulkStatsShmemRequest();
 
 	/* callbacks registered here should always be the first to run, so it's the last one we initialize */
 	zdb_aminit();
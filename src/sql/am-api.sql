@@ -95,3 +95,14 @@ CREATE OPERATOR CLASS tid_zdb_ops DEFAULT FOR TYPE tid USING zombodb AS
     OPERATOR 3 pg_catalog.==&(tid, zdbquery[]),
     OPERATOR 4 pg_catalog.==!(tid, zdbquery[]),
     STORAGE tid;
+
+--
+-- documents Elasticsearch rejected while their index was using on_error='skip'
+--
+CREATE TABLE dead_letters (
+  index regclass NOT NULL,
+  ctid tid,
+  reason text NOT NULL,
+  created timestamptz NOT NULL DEFAULT now()
+);
+SELECT pg_catalog.pg_extension_config_dump('dead_letters', '');
@@ -30,7 +30,37 @@ INSERT INTO zdb.type_conversions (typeoid, funcoid, is_default) VALUES ('point':
 INSERT INTO zdb.type_conversions (typeoid, funcoid, is_default) VALUES ('point[]'::regtype, 'zdb.point_array_to_json'::regproc, true);
 
 CREATE OR REPLACE FUNCTION zdb.bytea_to_json(bytea) RETURNS json PARALLEL SAFE IMMUTABLE STRICT LANGUAGE sql AS $$
-  SELECT to_json(encode($1, 'base64'));
+  SELECT to_json(translate(encode($1, 'base64'), E'\n', ''));  -- encode() wraps lines every 76 characters
 $$;
 
 INSERT INTO zdb.type_conversions (typeoid, funcoid, is_default) VALUES ('bytea'::regtype, 'zdb.bytea_to_json'::regproc, true);
+
+CREATE OR REPLACE FUNCTION zdb.inet_to_json(inet) RETURNS json PARALLEL SAFE IMMUTABLE STRICT LANGUAGE sql AS $$
+  SELECT to_json(host($1));  -- Elasticsearch's "ip" type doesn't accept a netmask
+$$;
+
+CREATE OR REPLACE FUNCTION zdb.inet_array_to_json(inet[]) RETURNS json PARALLEL SAFE IMMUTABLE STRICT LANGUAGE sql AS $$
+  SELECT json_agg(zdb.inet_to_json(addrs)) FROM unnest($1) AS addrs;
+$$;
+
+INSERT INTO zdb.type_conversions (typeoid, funcoid, is_default) VALUES ('inet'::regtype, 'zdb.inet_to_json'::regproc, true);
+INSERT INTO zdb.type_conversions (typeoid, funcoid, is_default) VALUES ('inet[]'::regtype, 'zdb.inet_array_to_json'::regproc, true);
+
+CREATE OR REPLACE FUNCTION zdb.interval_to_json(interval) RETURNS json PARALLEL SAFE IMMUTABLE STRICT LANGUAGE sql AS $$
+  SELECT to_json(trunc(extract(epoch FROM $1))::bigint);  -- like extract(), a month is 30 days and a year 365.25 days
+$$;
+
+CREATE OR REPLACE FUNCTION zdb.interval_array_to_json(interval[]) RETURNS json PARALLEL SAFE IMMUTABLE STRICT LANGUAGE sql AS $$
+  SELECT json_agg(zdb.interval_to_json(intervals)) FROM unnest($1) AS intervals;
+$$;
+
+CREATE OR REPLACE FUNCTION zdb.interval_to_iso8601_json(interval) RETURNS json PARALLEL SAFE IMMUTABLE STRICT LANGUAGE sql SET intervalstyle TO 'iso_8601' AS $$
+  SELECT to_json($1::text);
+$$;
+
+CREATE OR REPLACE FUNCTION zdb.interval_array_to_iso8601_json(interval[]) RETURNS json PARALLEL SAFE IMMUTABLE STRICT LANGUAGE sql AS $$
+  SELECT json_agg(zdb.interval_to_iso8601_json(intervals)) FROM unnest($1) AS intervals;
+$$;
+
+INSERT INTO zdb.type_conversions (typeoid, funcoid, is_default) VALUES ('interval'::regtype, 'zdb.interval_to_json'::regproc, true);
+INSERT INTO zdb.type_conversions (typeoid, funcoid, is_default) VALUES ('interval[]'::regtype, 'zdb.interval_array_to_json'::regproc, true);
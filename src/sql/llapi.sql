@@ -1,2 +1,3 @@
 CREATE OR REPLACE FUNCTION llapi_direct_insert(index_name regclass, data json) RETURNS void LANGUAGE c AS 'MODULE_PATHNAME', 'llapi_direct_insert';
 CREATE OR REPLACE FUNCTION llapi_direct_delete(index_name regclass, _id text) RETURNS void LANGUAGE c AS 'MODULE_PATHNAME', 'llapi_direct_delete';
+CREATE OR REPLACE FUNCTION bulk_insert(index_name regclass, documents jsonb[]) RETURNS bigint LANGUAGE c STRICT AS 'MODULE_PATHNAME', 'llapi_bulk_insert';
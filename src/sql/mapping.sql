@@ -190,6 +190,23 @@ INSERT INTO type_mappings(type_name, definition, is_default) VALUES (
     "type": "long"
   }', true);
 
+CREATE OR REPLACE FUNCTION numeric_type_mapping_func(datatype regtype, typmod integer) RETURNS jsonb PARALLEL SAFE IMMUTABLE STRICT LANGUAGE sql AS $$
+  --
+  -- numeric(p, s), where 'p' is at most 15, can be indexed as a scaled_float.  Elasticsearch
+  -- parses it as a double, which only holds 15 significant digits exactly, so anything wider (or
+  -- unconstrained) is a double, for range queries and aggregations, with its digits, as-is, in an
+  -- "exact" keyword subfield
+  --
+  SELECT CASE WHEN $2 < 4 OR (($2 - 4) >> 16) > 15 THEN
+            '{"type": "double", "fields": {"exact": {"type": "keyword"}}}'::jsonb
+         ELSE
+            jsonb_build_object('type', 'scaled_float', 'scaling_factor', (10 ^ (($2 - 4) & 65535))::bigint)
+         END
+$$;
+
+INSERT INTO type_mappings(type_name, funcid, is_default) VALUES (
+  'numeric', 'numeric_type_mapping_func', true);
+
 INSERT INTO type_mappings(type_name, definition, is_default) VALUES (
   'real', '{
     "type": "float"
@@ -241,19 +258,27 @@ INSERT INTO type_mappings(type_name, definition, is_default) VALUES (
 INSERT INTO type_mappings(type_name, definition, is_default) VALUES (
   'date', '{
     "type": "date",
-    "copy_to": "zdb_all"
+    "copy_to": "zdb_all",
+    "format": "strict_date_optional_time||epoch_millis"
   }', true);
 
 INSERT INTO type_mappings(type_name, definition, is_default) VALUES (
   'timestamp without time zone', '{
     "type": "date",
-    "copy_to": "zdb_all"
+    "copy_to": "zdb_all",
+    "format": "strict_date_optional_time||epoch_millis"
   }', true);
 
 INSERT INTO type_mappings(type_name, definition, is_default) VALUES (
   'timestamp with time zone', '{
     "type": "date",
-    "copy_to": "zdb_all"
+    "copy_to": "zdb_all",
+    "format": "strict_date_optional_time||epoch_millis"
+  }', true);
+
+INSERT INTO type_mappings(type_name, definition, is_default) VALUES (
+  'interval', '{
+    "type": "long"
   }', true);
 
 INSERT INTO type_mappings(type_name, definition, is_default) VALUES (
@@ -274,6 +299,18 @@ INSERT INTO type_mappings(type_name, definition, is_default) VALUES (
     "copy_to": "zdb_all"
   }', true);
 
+INSERT INTO type_mappings(type_name, definition, is_default) VALUES (
+  'cidr', '{
+    "type": "ip_range"
+  }', true);
+
+INSERT INTO type_mappings(type_name, definition, is_default) VALUES (
+  'uuid', '{
+    "type": "keyword",
+    "copy_to": "zdb_all",
+    "normalizer": "lowercase"
+  }', true);
+
 INSERT INTO type_mappings(type_name, definition, is_default) VALUES (
   'fulltext', '{
     "type": "text",
@@ -8,16 +8,119 @@ CREATE OR REPLACE FUNCTION ctid(ctid_as_64bits bigint) RETURNS tid PARALLEL SAFE
 -- for making arbitrary requests to the ES cluster backing the specified index
 --
 CREATE OR REPLACE FUNCTION request(index regclass, endpoint text, method text DEFAULT 'GET', post_data text DEFAULT NULL) RETURNS text PARALLEL SAFE STABLE LANGUAGE c AS 'MODULE_PATHNAME', 'zdb_request';
+CREATE OR REPLACE FUNCTION refresh(index regclass) RETURNS boolean PARALLEL UNSAFE VOLATILE STRICT LANGUAGE c AS 'MODULE_PATHNAME', 'zdb_refresh';
+CREATE OR REPLACE FUNCTION cleanup(index regclass) RETURNS bigint PARALLEL UNSAFE VOLATILE STRICT LANGUAGE c AS 'MODULE_PATHNAME', 'zdb_cleanup';
+CREATE OR REPLACE FUNCTION copy_index(index regclass) RETURNS bigint PARALLEL UNSAFE VOLATILE STRICT LANGUAGE c AS 'MODULE_PATHNAME', 'zdb_copy_index';
+CREATE OR REPLACE FUNCTION sync_mapping(index regclass) RETURNS void PARALLEL UNSAFE VOLATILE STRICT LANGUAGE c AS 'MODULE_PATHNAME', 'zdb_sync_mapping';
+CREATE OR REPLACE FUNCTION bulk_stats(shared boolean DEFAULT false) RETURNS TABLE (index regclass, requests bigint, documents bigint, version_conflicts bigint, latency_p50 bigint, latency_p95 bigint, latency_p99 bigint, avg_queue_depth float8, latency_sum bigint, latency_count bigint) PARALLEL UNSAFE VOLATILE LANGUAGE c AS 'MODULE_PATHNAME', 'zdb_bulk_stats';
+CREATE OR REPLACE FUNCTION internal_active_builds() RETURNS TABLE (pid integer, index regclass, started timestamptz, rows bigint, requests bigint, in_flight integer, bulk_concurrency integer) PARALLEL UNSAFE VOLATILE LANGUAGE c AS 'MODULE_PATHNAME', 'zdb_internal_active_builds';
+CREATE OR REPLACE FUNCTION metrics() RETURNS text PARALLEL UNSAFE VOLATILE LANGUAGE sql AS $$
+  WITH stats AS (
+      -- label values escape backslashes, double-quotes, and newlines
+      SELECT replace(replace(replace(index::text, '\', '\\'), '"', '\"'), E'\n', '\n') AS label, * FROM zdb.bulk_stats(shared => true)
+  ), families(ord, name, type, help) AS (
+      VALUES (1, 'zombodb_bulk_requests_total', 'counter', 'Number of _bulk requests sent to Elasticsearch'),
+             (2, 'zombodb_bulk_documents_total', 'counter', 'Number of documents sent in _bulk requests'),
+             (3, 'zombodb_bulk_version_conflicts_total', 'counter', 'Number of _bulk version conflicts ignored'),
+             (4, 'zombodb_bulk_latency_milliseconds', 'summary', '_bulk request latency, with approximate quantiles'),
+             (5, 'zombodb_bulk_queue_depth_average', 'gauge', 'Average number of _bulk requests already in flight when each was sent')
+  ), samples(ord, sample) AS (
+      SELECT 1, format('zombodb_bulk_requests_total{index="%s"} %s', label, requests) FROM stats
+      UNION ALL
+      SELECT 2, format('zombodb_bulk_documents_total{index="%s"} %s', label, documents) FROM stats
+      UNION ALL
+      SELECT 3, format('zombodb_bulk_version_conflicts_total{index="%s"} %s', label, version_conflicts) FROM stats
+      UNION ALL
+      SELECT 4, format('zombodb_bulk_latency_milliseconds{index="%s",quantile="%s"} %s', label, quantile, ms)
+        FROM stats, LATERAL (VALUES ('0.5', latency_p50), ('0.95', latency_p95), ('0.99', latency_p99)) AS p(quantile, ms)
+       WHERE ms IS NOT NULL
+      UNION ALL
+      SELECT 4, format('zombodb_bulk_latency_milliseconds_sum{index="%s"} %s', label, latency_sum) FROM stats
+      UNION ALL
+      SELECT 4, format('zombodb_bulk_latency_milliseconds_count{index="%s"} %s', label, latency_count) FROM stats
+      UNION ALL
+      SELECT 5, format('zombodb_bulk_queue_depth_average{index="%s"} %s', label, avg_queue_depth) FROM stats
+  )
+  SELECT string_agg(format(E'# HELP %s %s\n# TYPE %s %s\n', name, help, name, type)
+                        || coalesce((SELECT string_agg(sample || E'\n', '' ORDER BY sample COLLATE "C") FROM samples WHERE samples.ord = families.ord), ''),
+                    '' ORDER BY ord)
+    FROM families;
+$$;
+CREATE OR REPLACE FUNCTION validate_connection(url text, OUT reachable boolean, OUT cluster_name text, OUT version text, OUT message text) RETURNS record PARALLEL UNSAFE VOLATILE STRICT LANGUAGE c AS 'MODULE_PATHNAME', 'zdb_validate_connection';
+REVOKE EXECUTE ON FUNCTION validate_connection(text) FROM PUBLIC;
+CREATE OR REPLACE FUNCTION internal_compare_node_versions(url text, nodes json) RETURNS void PARALLEL UNSAFE VOLATILE STRICT LANGUAGE c AS 'MODULE_PATHNAME', 'zdb_internal_compare_node_versions';
+CREATE OR REPLACE FUNCTION benchmark_batch(index regclass, sizes int[], sample_rows bigint DEFAULT 10000) RETURNS TABLE (batch_size int, documents bigint, duration interval, documents_per_second numeric) PARALLEL UNSAFE VOLATILE STRICT LANGUAGE plpgsql AS $$
+DECLARE
+    heap_table regclass := (SELECT indrelid::regclass FROM pg_index WHERE indexrelid = index);
+    sample_table text := format('zdb_benchmark_batch_%s', index::oid);
+    -- a cloud_id index's url doesn't carry its credentials, so the copies need the cloud_id itself
+    connection text := coalesce((SELECT format('cloud_id=%L', option_value) FROM pg_class, pg_options_to_table(reloptions) WHERE oid = index AND option_name = 'cloud_id'),
+                                format('url=%L', zdb.index_url(index)));
+    size int;
+    started timestamptz;
+BEGIN
+    -- each size gets its own index on the same sample of rows, which all go away when we're done
+    EXECUTE format('CREATE TEMP TABLE %I AS SELECT * FROM %s LIMIT %s', sample_table, heap_table, sample_rows);
+    EXECUTE format('SELECT count(*) FROM %I', sample_table) INTO documents;
+
+    FOREACH size IN ARRAY sizes LOOP
+        started := clock_timestamp();
+        EXECUTE format('CREATE INDEX %I ON %I USING zombodb ((%I.*)) WITH (%s, batch_size=%s)',
+                       sample_table || '_idx', sample_table, sample_table, connection, size);
+
+        batch_size := size;
+        duration := clock_timestamp() - started;
+        documents_per_second := round(documents / greatest(extract(epoch FROM duration), 0.001)::numeric, 2);
+        RETURN NEXT;
+
+        EXECUTE format('DROP INDEX %I', sample_table || '_idx');
+    END LOOP;
+
+    EXECUTE format('DROP TABLE %I', sample_table);
+END;
+$$;
 
 --
 -- support functions
 --
 CREATE OR REPLACE FUNCTION index_name(index regclass) RETURNS text PARALLEL SAFE STABLE STRICT LANGUAGE c AS 'MODULE_PATHNAME', 'zdb_index_name';
 CREATE OR REPLACE FUNCTION index_url(index regclass) RETURNS text PARALLEL SAFE STABLE STRICT LANGUAGE c AS 'MODULE_PATHNAME', 'zdb_index_url';
+CREATE OR REPLACE FUNCTION cloud_id_url(cloud_id text) RETURNS text PARALLEL SAFE IMMUTABLE STRICT LANGUAGE c AS 'MODULE_PATHNAME', 'zdb_cloud_id_url';
 CREATE OR REPLACE FUNCTION index_type_name(index regclass) RETURNS text PARALLEL SAFE STABLE STRICT LANGUAGE c AS 'MODULE_PATHNAME', 'zdb_index_type_name';
 CREATE OR REPLACE FUNCTION index_mapping(index regclass) RETURNS json PARALLEL SAFE STABLE STRICT LANGUAGE sql AS $$
     SELECT (zdb.request(index, '_mapping?pretty')::json)->zdb.index_name(index);
 $$;
+CREATE OR REPLACE FUNCTION index_properties(index regclass) RETURNS jsonb PARALLEL SAFE STABLE STRICT LANGUAGE plpgsql AS $$
+DECLARE
+    mapping jsonb := zdb.request(index, '_mapping')::jsonb;
+BEGIN
+    IF mapping ? 'error' THEN
+        RAISE EXCEPTION 'the Elasticsearch index for ''%'' does not exist', index;
+    END IF;
+
+    RETURN mapping->zdb.index_name(index)->'mappings'->zdb.index_type_name(index)->'properties';
+END;
+$$;
+CREATE OR REPLACE FUNCTION copy_mapping(src regclass, dst regclass) RETURNS void PARALLEL UNSAFE VOLATILE STRICT LANGUAGE plpgsql AS $$
+DECLARE
+    src_properties jsonb := zdb.index_properties(src);
+    dst_properties jsonb := zdb.index_properties(dst);
+    dst_field_mapping jsonb := (SELECT option_value::jsonb FROM pg_options_to_table((SELECT reloptions FROM pg_class WHERE oid = dst)) WHERE option_name = 'field_mapping');
+    field_mapping jsonb;
+BEGIN
+    -- the fields both indices have, other than the ones ZomboDB manages itself
+    SELECT jsonb_object_agg(key, value) INTO field_mapping
+      FROM jsonb_each(src_properties)
+     WHERE dst_properties ? key AND key NOT LIKE 'zdb\_%';
+
+    -- and whatever else dst's own field_mapping already says
+    EXECUTE format('ALTER INDEX %s SET (field_mapping=%L)', dst, coalesce(dst_field_mapping, '{}') || coalesce(field_mapping, '{}'));
+    EXECUTE format('REINDEX INDEX %s', dst);
+END;
+$$;
+CREATE OR REPLACE FUNCTION term_vectors(index regclass, ctid tid, field text) RETURNS jsonb PARALLEL SAFE STABLE STRICT LANGUAGE c AS 'MODULE_PATHNAME', 'zdb_term_vectors';
+CREATE OR REPLACE FUNCTION explain(index regclass, query zdbquery, ctid tid) RETURNS jsonb PARALLEL SAFE STABLE STRICT LANGUAGE c AS 'MODULE_PATHNAME', 'zdb_explain';
+CREATE OR REPLACE FUNCTION get_document(index regclass, ctid tid) RETURNS jsonb PARALLEL SAFE STABLE STRICT LANGUAGE c AS 'MODULE_PATHNAME', 'zdb_get_document';
 CREATE OR REPLACE FUNCTION all_es_index_names() RETURNS SETOF text PARALLEL SAFE STABLE STRICT LANGUAGE sql AS $$
     SELECT zdb.index_name(oid::regclass) FROM pg_class WHERE relam = (SELECT oid FROM pg_am WHERE amname = 'zombodb');
 $$;
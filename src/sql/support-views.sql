@@ -29,3 +29,8 @@ CREATE OR REPLACE VIEW index_stats AS
     (zdb.request(indexrelid, 'doc/_count', 'GET') :: JSON) -> 'count'                      AS doc_count,
     coalesce(json_array_length((zdb.request(indexrelid, 'doc/zdb_aborted_xids', 'GET') :: JSON) -> '_source'->'zdb_aborted_xids'), 0) AS aborted_xids
   FROM stats;
+
+--
+-- the CREATE INDEX and REINDEX statements in progress in this database, across all sessions
+--
+CREATE OR REPLACE VIEW active_builds AS SELECT * FROM zdb.internal_active_builds();
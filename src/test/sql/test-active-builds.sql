@@ -0,0 +1,25 @@
+CREATE TABLE active_builds (
+    id serial8 not null primary key,
+    n int
+);
+INSERT INTO active_builds (n) SELECT i FROM generate_series(1, 10) i;
+
+-- the transform function runs for each row in the middle of the build, so it sees this backend's own build in the view
+CREATE FUNCTION active_builds_peek(doc jsonb) RETURNS jsonb VOLATILE LANGUAGE sql AS $$
+    SELECT doc || jsonb_build_object('building', (SELECT index::text FROM zdb.active_builds WHERE pid = pg_backend_pid()));
+$$;
+CREATE INDEX idxactive_builds ON active_builds USING zombodb ((active_builds.*)) WITH (transform_function='active_builds_peek');
+SELECT count(*) FROM active_builds WHERE active_builds ==> 'building:idxactive_builds';
+SELECT count(*) FROM zdb.active_builds WHERE pid = pg_backend_pid();
+
+-- a build that fails is gone from the view too
+CREATE FUNCTION active_builds_fail(doc jsonb) RETURNS jsonb VOLATILE LANGUAGE sql AS $$
+    SELECT CASE WHEN (doc->>'n')::int = 5 THEN '{}'::jsonb ELSE doc END;
+$$;
+ALTER INDEX idxactive_builds SET (transform_function='active_builds_fail');
+REINDEX INDEX idxactive_builds;
+SELECT count(*) FROM zdb.active_builds WHERE pid = pg_backend_pid();
+
+DROP TABLE active_builds;
+DROP FUNCTION active_builds_peek(jsonb);
+DROP FUNCTION active_builds_fail(jsonb);
@@ -0,0 +1,26 @@
+CREATE TABLE batch_max_age (
+    id serial8 not null primary key,
+    title text
+);
+CREATE INDEX idxbatch_max_age ON batch_max_age USING zombodb ((batch_max_age.*));
+
+-- without a max age, the row waits in its batch until the transaction ends
+BEGIN;
+INSERT INTO batch_max_age (title) VALUES ('waiting');
+SELECT pg_sleep(0.1);
+SELECT zdb.request('idxbatch_max_age', '_refresh', 'POST') IS NOT NULL AS refreshed;
+SELECT zdb.request('idxbatch_max_age', '_count?q=title:waiting')::json->'count' AS count;
+COMMIT;
+
+-- with one, a statement that ends after the batch has aged sends it, even though no other row arrived
+BEGIN;
+SET LOCAL zdb.batch_max_age TO 1;
+INSERT INTO batch_max_age (title) VALUES ('aged');
+SELECT pg_sleep(0.1);
+SELECT zdb.request('idxbatch_max_age', '_refresh', 'POST') IS NOT NULL AS refreshed;
+SELECT zdb.request('idxbatch_max_age', '_count?q=title:aged')::json->'count' AS count;
+COMMIT;
+
+SELECT title FROM batch_max_age WHERE batch_max_age ==> 'title:(waiting, aged)' ORDER BY id;
+
+DROP TABLE batch_max_age;
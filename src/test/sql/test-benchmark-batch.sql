@@ -0,0 +1,5 @@
+SELECT batch_size, documents FROM zdb.benchmark_batch('idxevents', ARRAY[1024, 8388608], 100);
+\set VERBOSITY terse
+SELECT batch_size, documents FROM zdb.benchmark_batch('idxevents', ARRAY[1], 100);
+\set VERBOSITY default
+SELECT count(*) FROM pg_class WHERE relname LIKE 'zdb_benchmark_batch_%';
@@ -0,0 +1,38 @@
+CREATE TABLE build_memory (
+    id serial8 not null primary key,
+    title text
+);
+INSERT INTO build_memory (title) VALUES ('one'), ('two');
+-- big enough that zdb.small_table_threshold doesn't apply, without having more than two live rows to build from
+INSERT INTO build_memory (title) SELECT 'row ' || x FROM generate_series(1, 20000) x;
+ANALYZE build_memory;
+DELETE FROM build_memory WHERE title LIKE 'row %';
+SET zdb.log_level TO 'notice';
+SET zdb.bulk_concurrency_per_node TO 16;
+
+-- explicit options are used as they are, with a warning
+SET maintenance_work_mem TO '8MB';
+CREATE INDEX idxbuild_memory ON build_memory USING zombodb ((build_memory.*)) WITH (batch_size=2097152, bulk_concurrency=12);
+DROP INDEX idxbuild_memory;
+
+-- only an automatic bulk_concurrency is lowered
+CREATE INDEX idxbuild_memory ON build_memory USING zombodb ((build_memory.*)) WITH (batch_size=2097152, bulk_concurrency=0);
+DROP INDEX idxbuild_memory;
+
+SET maintenance_work_mem TO '16MB';
+CREATE INDEX idxbuild_memory ON build_memory USING zombodb ((build_memory.*)) WITH (batch_size=2097152, bulk_concurrency=0);
+DROP INDEX idxbuild_memory;
+
+-- and the default batch_size with it, when even one request won't fit
+SET maintenance_work_mem TO '1MB';
+CREATE INDEX idxbuild_memory ON build_memory USING zombodb ((build_memory.*)) WITH (bulk_concurrency=0);
+DROP INDEX idxbuild_memory;
+
+-- the stock settings are left alone
+RESET maintenance_work_mem;
+CREATE INDEX idxbuild_memory ON build_memory USING zombodb ((build_memory.*));
+SELECT id, title FROM build_memory WHERE build_memory ==> 'title:two' ORDER BY id;
+
+RESET zdb.bulk_concurrency_per_node;
+RESET zdb.log_level;
+DROP TABLE build_memory;
@@ -0,0 +1,10 @@
+CREATE TABLE bulk_concurrency_auto AS SELECT id, 'row ' || id AS title FROM generate_series(1, 1000) id;
+CREATE INDEX idxbulk_concurrency_auto ON bulk_concurrency_auto USING zombodb ((bulk_concurrency_auto.*)) WITH (bulk_concurrency=0, batch_size=1024);
+SELECT zdb.count('idxbulk_concurrency_auto', dsl.match_all());
+
+SET zdb.bulk_concurrency_per_node TO 0;
+SET zdb.bulk_concurrency_per_node TO 2;
+INSERT INTO bulk_concurrency_auto SELECT id, 'row ' || id FROM generate_series(1001, 1100) id;
+SELECT zdb.count('idxbulk_concurrency_auto', dsl.match_all());
+
+DROP TABLE bulk_concurrency_auto;
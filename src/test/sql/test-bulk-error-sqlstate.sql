@@ -0,0 +1,16 @@
+CREATE TABLE bulk_error_sqlstate (
+    id serial8 not null primary key,
+    code varchar
+);
+CREATE INDEX idxbulk_error_sqlstate ON bulk_error_sqlstate USING zombodb ((bulk_error_sqlstate.*)) WITH (field_mapping='{"code": {"type": "integer"}}');
+
+DO LANGUAGE plpgsql $$
+BEGIN
+    INSERT INTO bulk_error_sqlstate (code) VALUES ('not a number');
+    PERFORM count(*) FROM bulk_error_sqlstate WHERE bulk_error_sqlstate ==> 'code:1';
+EXCEPTION WHEN data_exception THEN
+    RAISE NOTICE 'Elasticsearch couldn''t parse the document: %', SQLSTATE;
+END;
+$$;
+
+DROP TABLE bulk_error_sqlstate;
@@ -0,0 +1,16 @@
+SHOW zdb.bulk_filter_path;
+SET zdb.bulk_filter_path TO 'items.*.error';
+
+CREATE TABLE bulk_filter_path (
+    id serial8 not null primary key,
+    title text
+);
+CREATE INDEX idxbulk_filter_path ON bulk_filter_path USING zombodb ((bulk_filter_path.*));
+
+SET zdb.bulk_filter_path TO 'errors,items';
+INSERT INTO bulk_filter_path (title) VALUES ('one'), ('two');
+UPDATE bulk_filter_path SET title = 'three' WHERE title = 'two';
+SELECT id, title FROM bulk_filter_path WHERE bulk_filter_path ==> 'title:(one OR three)' ORDER BY id;
+
+RESET zdb.bulk_filter_path;
+DROP TABLE bulk_filter_path;
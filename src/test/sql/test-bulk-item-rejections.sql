@@ -0,0 +1,23 @@
+--
+-- the test cluster runs with a small thread_pool.bulk.queue_size (see .travis.yml), so a _bulk request that
+-- touches many shards at once has some of its documents rejected, which ZomboDB sends again by themselves
+--
+CREATE TABLE bulk_item_rejections (
+    id serial8 not null primary key,
+    title text
+);
+INSERT INTO bulk_item_rejections (title) SELECT 'row ' || x FROM generate_series(1, 1000) x;
+ANALYZE bulk_item_rejections;
+
+CREATE TABLE bulk_item_rejections_before AS
+    SELECT sum((x->>'rejected')::bigint) AS rejected FROM json_array_elements(zdb.request('idxevents', '/_cat/thread_pool/bulk,write?h=rejected&format=json')::json) x;
+
+CREATE INDEX idxbulk_item_rejections ON bulk_item_rejections USING zombodb ((bulk_item_rejections.*)) WITH (shards=64, bulk_concurrency=4, on_error='skip');
+
+SELECT sum((x->>'rejected')::bigint) > (SELECT rejected FROM bulk_item_rejections_before) AS rejected_some
+  FROM json_array_elements(zdb.request('idxevents', '/_cat/thread_pool/bulk,write?h=rejected&format=json')::json) x;
+SELECT count(*) AS dead_letters FROM zdb.dead_letters WHERE index = 'idxbulk_item_rejections'::regclass;
+SELECT zdb.count('idxbulk_item_rejections', dsl.match_all());
+
+DROP TABLE bulk_item_rejections_before;
+DROP TABLE bulk_item_rejections;
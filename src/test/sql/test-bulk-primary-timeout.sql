@@ -0,0 +1,17 @@
+CREATE TABLE bulk_primary_timeout (
+    id serial8 not null primary key,
+    title text
+);
+
+CREATE INDEX idxbulk_primary_timeout ON bulk_primary_timeout USING zombodb ((bulk_primary_timeout.*)) WITH (bulk_primary_timeout='soon');
+CREATE INDEX idxbulk_primary_timeout ON bulk_primary_timeout USING zombodb ((bulk_primary_timeout.*)) WITH (bulk_primary_timeout='30');
+CREATE INDEX idxbulk_primary_timeout ON bulk_primary_timeout USING zombodb ((bulk_primary_timeout.*)) WITH (bulk_primary_timeout='30s');
+
+INSERT INTO bulk_primary_timeout (title) VALUES ('one'), ('two');
+SELECT id, title FROM bulk_primary_timeout WHERE bulk_primary_timeout ==> 'title:two' ORDER BY id;
+
+ALTER INDEX idxbulk_primary_timeout SET (bulk_primary_timeout='2m');
+INSERT INTO bulk_primary_timeout (title) VALUES ('three');
+SELECT id, title FROM bulk_primary_timeout WHERE bulk_primary_timeout ==> 'title:three' ORDER BY id;
+
+DROP TABLE bulk_primary_timeout;
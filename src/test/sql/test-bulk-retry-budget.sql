@@ -0,0 +1,32 @@
+CREATE TABLE bulk_retry_budget (
+    id serial8 not null primary key,
+    title text
+);
+CREATE INDEX idxbulk_retry_budget ON bulk_retry_budget USING zombodb ((bulk_retry_budget.*));
+
+\set VERBOSITY terse
+SET zdb.bulk_retry_budget TO -1;
+\set VERBOSITY default
+
+-- a budget doesn't get in the way of requests that don't need retrying
+SET zdb.bulk_retry_budget TO 1;
+INSERT INTO bulk_retry_budget (title) SELECT 'row ' || x FROM generate_series(1, 1000) x;
+SELECT count(*) FROM bulk_retry_budget WHERE bulk_retry_budget ==> 'title:row';
+
+RESET zdb.bulk_retry_budget;
+DROP TABLE bulk_retry_budget;
+
+-- the test cluster runs with a small thread_pool.bulk.queue_size (see .travis.yml), so building an index with
+-- many shards has documents rejected more than once, which is more than a budget of one retry allows
+CREATE TABLE bulk_retry_budget_exhausted (
+    id serial8 not null primary key,
+    title text
+);
+INSERT INTO bulk_retry_budget_exhausted (title) SELECT 'row ' || x FROM generate_series(1, 1000) x;
+ANALYZE bulk_retry_budget_exhausted;
+SET zdb.bulk_retry_budget TO 1;
+\set VERBOSITY terse
+CREATE INDEX idxbulk_retry_budget_exhausted ON bulk_retry_budget_exhausted USING zombodb ((bulk_retry_budget_exhausted.*)) WITH (shards=64, bulk_concurrency=4);
+\set VERBOSITY default
+RESET zdb.bulk_retry_budget;
+DROP TABLE bulk_retry_budget_exhausted;
@@ -0,0 +1,35 @@
+CREATE TABLE bulk_stats (
+    id serial8 not null primary key,
+    title text
+);
+CREATE INDEX idxbulk_stats ON bulk_stats USING zombodb ((bulk_stats.*));
+SELECT * FROM zdb.bulk_stats() WHERE index = 'idxbulk_stats'::regclass;
+
+INSERT INTO bulk_stats (title) SELECT 'row ' || x FROM generate_series(1, 10) x;
+SELECT index, requests > 0 AS sent, documents >= 10 AS all_documents, version_conflicts FROM zdb.bulk_stats() WHERE index = 'idxbulk_stats'::regclass;
+SELECT index, latency_p50 >= 0 AS p50, latency_p95 >= latency_p50 AS p95, latency_p99 >= latency_p95 AS p99 FROM zdb.bulk_stats() WHERE index = 'idxbulk_stats'::regclass;
+
+-- a build with a tiny batch_size sends many requests, and with bulk_concurrency=1 no more than one is ever in flight
+CREATE TABLE bulk_stats_depth (
+    id serial8 not null primary key,
+    title text
+);
+INSERT INTO bulk_stats_depth (title) SELECT repeat('x', 100) FROM generate_series(1, 100);
+CREATE INDEX idxbulk_stats_depth ON bulk_stats_depth USING zombodb ((bulk_stats_depth.*)) WITH (batch_size=1024, bulk_concurrency=1);
+SELECT index, requests >= 10 AS many_requests, documents >= 100 AS all_documents, avg_queue_depth BETWEEN 0 AND 1 AS within_concurrency FROM zdb.bulk_stats() WHERE index = 'idxbulk_stats_depth'::regclass;
+DROP TABLE bulk_stats_depth;
+
+-- creating a document where one already exists is a version conflict, which on_error='skip' passes over.  (0, 11) is the ctid of the next row
+ALTER INDEX idxbulk_stats SET (index_action='create', on_error='skip');
+SELECT zdb.request('idxbulk_stats', 'doc/11', 'PUT', '{"title": "planted"}')::json->>'result' AS result;
+INSERT INTO bulk_stats (title) VALUES ('conflicted');
+SELECT index, version_conflicts FROM zdb.bulk_stats() WHERE index = 'idxbulk_stats'::regclass;
+SELECT ctid, reason LIKE 'version_conflict_engine_exception:%' AS conflict FROM zdb.dead_letters WHERE index = 'idxbulk_stats'::regclass;
+DELETE FROM zdb.dead_letters WHERE index = 'idxbulk_stats'::regclass;
+
+-- a dropped index's stats go away with it, so an index that later gets the same oid starts from zero
+CREATE TEMP TABLE bulk_stats_dropped AS SELECT 'idxbulk_stats'::regclass::oid AS oid;
+DROP TABLE bulk_stats;
+SELECT count(*) FROM zdb.bulk_stats() WHERE index::oid IN (SELECT oid FROM bulk_stats_dropped);
+SELECT count(*) FROM zdb.bulk_stats(shared => true) WHERE index::oid IN (SELECT oid FROM bulk_stats_dropped);
+DROP TABLE bulk_stats_dropped;
@@ -0,0 +1,18 @@
+CREATE TABLE bulk_too_large (
+    id serial8 not null primary key,
+    body text
+);
+SELECT zdb.define_field_mapping('bulk_too_large', 'body', '{"type": "keyword", "index": false, "doc_values": false}');
+CREATE INDEX idxbulk_too_large ON bulk_too_large USING zombodb ((bulk_too_large.*)) WITH (batch_size=268435456);
+
+-- Elasticsearch's http.max_content_length defaults to 100mb, so it rejects this batch, which is then sent again in pieces
+INSERT INTO bulk_too_large (body) SELECT repeat('x', 1048576) FROM generate_series(1, 120);
+SELECT count(*) FROM bulk_too_large WHERE bulk_too_large ==> dsl.match_all();
+
+-- but a document that's too large by itself can't be split
+\set VERBOSITY terse
+INSERT INTO bulk_too_large (body) VALUES (repeat('x', 110 * 1048576));
+\set VERBOSITY default
+SELECT count(*) FROM bulk_too_large WHERE bulk_too_large ==> dsl.match_all();
+
+DROP TABLE bulk_too_large;
@@ -17,4 +17,9 @@ SELECT * FROM bytea_to_json WHERE bytea_to_json ==> dsl.script(
 );
 
 
+-- empty and multi-line (when encoded) values are plain base64 strings
+SELECT zdb.bytea_to_json(''::bytea) AS empty, zdb.bytea_to_json(decode(repeat('00ff', 60), 'hex')) AS long;
+INSERT INTO bytea_to_json (data) VALUES (''), (decode(repeat('00ff', 60), 'hex'));
+SELECT id FROM bytea_to_json WHERE bytea_to_json ==> dsl.script($$ doc['data'].value.length == 120 $$);
+
 DROP TABLE bytea_to_json;
\ No newline at end of file
@@ -0,0 +1,16 @@
+CREATE TABLE cancel_bulk (
+    id serial8 not null primary key,
+    title text
+);
+CREATE INDEX idxcancel_bulk ON cancel_bulk USING zombodb ((cancel_bulk.*)) WITH (bulk_concurrency=1, batch_size=16384);
+CREATE TEMP TABLE cancel_started AS SELECT clock_timestamp() AS at;
+
+-- most of this is spent waiting for Elasticsearch to finish the previous batch, which a timeout must interrupt
+SET statement_timeout TO '250ms';
+INSERT INTO cancel_bulk (title) SELECT 'row ' || x FROM generate_series(1, 1000000) x;
+RESET statement_timeout;
+SELECT clock_timestamp() - at < interval '2 seconds' AS canceled_promptly FROM cancel_started;
+SELECT count(*) FROM cancel_bulk;
+
+DROP TABLE cancel_started;
+DROP TABLE cancel_bulk;
@@ -0,0 +1,34 @@
+CREATE TABLE check_node_versions (
+    id serial8 not null primary key,
+    title text
+);
+INSERT INTO check_node_versions (title) VALUES ('one');
+
+-- every node in the test cluster runs the same version, so the build goes ahead
+SET zdb.check_node_versions TO 'error';
+CREATE INDEX idxcheck_node_versions ON check_node_versions USING zombodb ((check_node_versions.*));
+SELECT id, title FROM check_node_versions WHERE check_node_versions ==> 'one' ORDER BY id;
+
+SET zdb.check_node_versions TO 'warning';
+REINDEX INDEX idxcheck_node_versions;
+SELECT id, title FROM check_node_versions WHERE check_node_versions ==> 'one' ORDER BY id;
+
+\set VERBOSITY terse
+SET zdb.check_node_versions TO 'sometimes';
+\set VERBOSITY default
+
+
+-- the comparison by itself, with a response from a cluster in the middle of an upgrade
+\set VERBOSITY terse
+SET zdb.check_node_versions TO 'warning';
+SELECT zdb.internal_compare_node_versions('http://localhost:9200/', '{"nodes": {"a": {"name": "es1", "version": "6.2.4"}, "b": {"name": "es2", "version": "5.6.9"}}}');
+SELECT zdb.internal_compare_node_versions('http://localhost:9200/', '{"nodes": {"a": {"name": "es1", "version": "6.2.4"}, "b": {"name": "es2", "version": "6.8.1"}}}');
+SET zdb.check_node_versions TO 'error';
+SELECT zdb.internal_compare_node_versions('http://localhost:9200/', '{"nodes": {"a": {"name": "es1", "version": "6.2.4"}, "b": {"name": "es2", "version": "5.6.9"}}}');
+SELECT zdb.internal_compare_node_versions('http://localhost:9200/', '{"nodes": {"a": {"name": "es1", "version": "6.2.4"}, "b": {"name": "es2", "version": "6.8.1"}}}');
+SET zdb.check_node_versions TO 'off';
+SELECT zdb.internal_compare_node_versions('http://localhost:9200/', '{"nodes": {"a": {"name": "es1", "version": "6.2.4"}, "b": {"name": "es2", "version": "5.6.9"}}}');
+\set VERBOSITY default
+
+RESET zdb.check_node_versions;
+DROP TABLE check_node_versions;
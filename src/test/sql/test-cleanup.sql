@@ -0,0 +1,15 @@
+CREATE TABLE cleanup (
+    id serial8 not null primary key,
+    title text
+);
+CREATE INDEX idxcleanup ON cleanup USING zombodb ((cleanup.*));
+INSERT INTO cleanup (title) VALUES ('one'), ('two'), ('three');
+DELETE FROM cleanup WHERE title = 'two';
+UPDATE cleanup SET title = 'THREE' WHERE title = 'three';
+
+SELECT zdb.cleanup('idxcleanup');
+SELECT zdb.cleanup('idxcleanup');
+
+SELECT * FROM cleanup WHERE cleanup ==> dsl.match_all() ORDER BY id;
+
+DROP TABLE cleanup;
@@ -0,0 +1,19 @@
+CREATE TABLE cloud_id (
+    id serial8 not null primary key,
+    title text
+);
+
+CREATE INDEX idxcloud_id ON cloud_id USING zombodb ((cloud_id.*)) WITH (cloud_id='my_cluster:not base64!');
+CREATE INDEX idxcloud_id ON cloud_id USING zombodb ((cloud_id.*)) WITH (cloud_id='my_cluster:dXMtZWFzdC0xLmF3cy5mb3VuZC5pbw==');
+CREATE INDEX idxcloud_id ON cloud_id USING zombodb ((cloud_id.*)) WITH (cloud_id='my_cluster:');
+CREATE INDEX idxcloud_id ON cloud_id USING zombodb ((cloud_id.*)) WITH (cloud_id='my_cluster:dXMtZWFzdC0xLmF3cy5mb3VuZC5pbyRjZWM2ZjI2MWE3NGJmMjRjZTMzYmI4ODExYjg0Mjk0ZiRjNmMyY2E2ZDA0MjI0OWFmMGNjN2Q3YTllOTYyNTc0Mw==', cloud_auth='elastic:changeme');
+CREATE INDEX idxcloud_id ON cloud_id USING zombodb ((cloud_id.*)) WITH (url='http://localhost:9200/', cloud_id='my_cluster:dXMtZWFzdC0xLmF3cy5mb3VuZC5pbyRjZWM2ZjI2MWE3NGJmMjRjZTMzYmI4ODExYjg0Mjk0ZiRjNmMyY2E2ZDA0MjI0OWFmMGNjN2Q3YTllOTYyNTc0Mw==');
+
+-- the host, the Elasticsearch uuid, and an optional port decode into the url, which never includes credentials
+SELECT zdb.cloud_id_url('my_cluster:dXMtZWFzdC0xLmF3cy5mb3VuZC5pbyRjZWM2ZjI2MWE3NGJmMjRjZTMzYmI4ODExYjg0Mjk0ZiRjNmMyY2E2ZDA0MjI0OWFmMGNjN2Q3YTllOTYyNTc0Mw==');
+SELECT zdb.cloud_id_url('other:ZXUtd2VzdC0xLmF3cy5mb3VuZC5pbzo5MjQzJDRmYTg4MjFlNzU2MzQwMzJiZWQxY2YyMjExMGUyZjk3JDRmYTg4MjFlNzU2MzQwMzJiZWQxY2YyMjExMGUyZjk2');
+
+-- credentials only come from the superuser-only setting, and never become part of the url
+SET zdb.cloud_auth TO 'elastic';
+
+DROP TABLE cloud_id;
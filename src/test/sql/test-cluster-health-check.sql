@@ -0,0 +1,12 @@
+CREATE TABLE cluster_health_check (
+    id serial8 not null primary key,
+    title text
+);
+INSERT INTO cluster_health_check (title) VALUES ('one');
+
+-- nothing is listening here, so the build must fail before it does anything else
+\set VERBOSITY terse
+CREATE INDEX idxcluster_health_check ON cluster_health_check USING zombodb ((cluster_health_check.*)) WITH (url='http://localhost:1/');
+\set VERBOSITY default
+
+DROP TABLE cluster_health_check;
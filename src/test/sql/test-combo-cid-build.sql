@@ -0,0 +1,25 @@
+--
+-- rows inserted and then updated (or deleted by a rolled back savepoint) in the same transaction have a combo
+-- command id, which a CREATE INDEX later in that transaction has to resolve to see them correctly
+--
+BEGIN;
+CREATE TABLE combo_cid_build (
+    id serial8 not null primary key,
+    title text
+);
+INSERT INTO combo_cid_build (title) VALUES ('one'), ('two'), ('three');
+SAVEPOINT s;
+DELETE FROM combo_cid_build WHERE id = 2;
+ROLLBACK TO SAVEPOINT s;
+UPDATE combo_cid_build SET title = 'updated three' WHERE id = 3;
+
+CREATE INDEX idxcombo_cid_build ON combo_cid_build USING zombodb ((combo_cid_build.*));
+SELECT id, title FROM combo_cid_build WHERE combo_cid_build ==> dsl.match_all() ORDER BY id;
+
+UPDATE combo_cid_build SET title = 'updated one' WHERE id = 1;
+SELECT id, title FROM combo_cid_build WHERE combo_cid_build ==> dsl.match_all() ORDER BY id;
+COMMIT;
+
+SELECT id, title FROM combo_cid_build WHERE combo_cid_build ==> dsl.match_all() ORDER BY id;
+
+DROP TABLE combo_cid_build;
@@ -0,0 +1,28 @@
+CREATE TYPE composite_mapping_address AS (
+    street varchar,
+    city varchar
+);
+
+CREATE TABLE composite_mapping (
+    id serial8 not null primary key,
+    name text,
+    home composite_mapping_address,
+    previous composite_mapping_address[]
+);
+
+CREATE INDEX idxcomposite_mapping ON composite_mapping USING zombodb ((composite_mapping.*));
+SELECT (zdb.index_mapping('idxcomposite_mapping')->'mappings'->'doc'->'properties'->'home')::jsonb;
+SELECT (zdb.index_mapping('idxcomposite_mapping')->'mappings'->'doc'->'properties'->'previous'->'type')::jsonb;
+
+INSERT INTO composite_mapping (name, home, previous) VALUES ('one', ROW('Main St', 'Springfield'), ARRAY[ROW('Elm St', 'Shelbyville'), ROW('Oak St', 'Capital City')]::composite_mapping_address[]);
+INSERT INTO composite_mapping (name, home, previous) VALUES ('two', ROW('Oak St', 'Shelbyville'), ARRAY[ROW('Elm St', 'Capital City')]::composite_mapping_address[]);
+
+SELECT id, name FROM composite_mapping WHERE composite_mapping ==> 'home.city:springfield' ORDER BY id;
+
+-- include_in_parent flattens the addresses into the row too, so a plain query matches a street from one and a city from another
+SELECT id, name FROM composite_mapping WHERE composite_mapping ==> 'previous.street:"elm st" AND previous.city:"capital city"' ORDER BY id;
+-- but a nested query only matches when the street and city belong to the same previous address
+SELECT id, name FROM composite_mapping WHERE composite_mapping ==> dsl.nested('previous', dsl.and(dsl.term('previous.street', 'elm st'), dsl.term('previous.city', 'capital city'))) ORDER BY id;
+
+DROP TABLE composite_mapping;
+DROP TYPE composite_mapping_address;
@@ -0,0 +1,18 @@
+CREATE TABLE copy_index (
+    id serial8 not null primary key,
+    title text
+);
+INSERT INTO copy_index (title) VALUES ('one'), ('two'), ('three');
+CREATE INDEX idxcopy_index ON copy_index USING zombodb ((copy_index.*));
+
+SELECT (zdb.request('idxcopy_index', '_delete_by_query?refresh=true', 'POST', '{"query":{"exists":{"field":"zdb_ctid"}}}')::jsonb)->'deleted' AS deleted;
+SELECT zdb.count('idxcopy_index', match_all()) AS documents;
+
+SELECT zdb.copy_index('idxcopy_index');
+SELECT zdb.count('idxcopy_index', match_all()) AS documents;
+SELECT id, title FROM copy_index WHERE copy_index ==> 'title:two' ORDER BY id;
+
+SELECT zdb.copy_index('idxcopy_index');
+SELECT zdb.count('idxcopy_index', match_all()) AS documents;
+
+DROP TABLE copy_index;
@@ -0,0 +1,26 @@
+CREATE TABLE copy_mapping_src (
+    id serial8 not null primary key,
+    title text,
+    sku varchar
+);
+CREATE TABLE copy_mapping_dst (
+    id serial8 not null primary key,
+    title text,
+    sku varchar,
+    notes text
+);
+CREATE INDEX idxcopy_mapping_src ON copy_mapping_src USING zombodb ((copy_mapping_src.*)) WITH (field_mapping='{"title": {"type": "text", "analyzer": "english"}, "sku": {"type": "keyword", "index": false}}');
+CREATE INDEX idxcopy_mapping_dst ON copy_mapping_dst USING zombodb ((copy_mapping_dst.*)) WITH (field_mapping='{"notes": {"type": "keyword"}}');
+INSERT INTO copy_mapping_dst (title, sku) VALUES ('The runners were running', 'ABC-123');
+
+SELECT zdb.index_properties('idxcopy_mapping_dst')->'title' = zdb.index_properties('idxcopy_mapping_src')->'title' AS same_title;
+SELECT zdb.copy_mapping('idxcopy_mapping_src', 'idxcopy_mapping_dst');
+SELECT zdb.index_properties('idxcopy_mapping_dst')->'title' = zdb.index_properties('idxcopy_mapping_src')->'title' AS same_title;
+SELECT zdb.index_properties('idxcopy_mapping_dst')->'sku' = zdb.index_properties('idxcopy_mapping_src')->'sku' AS same_sku;
+-- dst's mapping for fields src doesn't have is kept
+SELECT zdb.index_properties('idxcopy_mapping_dst')->'notes'->>'type' AS notes_type;
+SELECT zdb.index_properties('idxcopy_mapping_dst')->'title';
+SELECT id, title FROM copy_mapping_dst WHERE copy_mapping_dst ==> 'title:run' ORDER BY id;
+
+DROP TABLE copy_mapping_src;
+DROP TABLE copy_mapping_dst;
@@ -0,0 +1,17 @@
+CREATE TABLE default_analyzer (
+    id serial8 not null primary key,
+    title text,
+    tags text[],
+    body zdb.fulltext,
+    sku varchar
+);
+
+CREATE INDEX idxdefault_analyzer ON default_analyzer USING zombodb ((default_analyzer.*)) WITH (default_analyzer='eng lish');
+CREATE INDEX idxdefault_analyzer ON default_analyzer USING zombodb ((default_analyzer.*)) WITH (default_analyzer='english');
+SELECT key, value->>'analyzer' AS analyzer FROM json_each(zdb.index_mapping('idxdefault_analyzer')->'mappings'->'doc'->'properties') WHERE key IN ('title', 'tags', 'body', 'sku') ORDER BY key;
+
+INSERT INTO default_analyzer (title) VALUES ('running with scissors'), ('ran away');
+-- the english analyzer stems "running" to "run"
+SELECT id, title FROM default_analyzer WHERE default_analyzer ==> 'title:run' ORDER BY id;
+
+DROP TABLE default_analyzer;
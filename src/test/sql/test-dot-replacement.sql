@@ -0,0 +1,16 @@
+CREATE TABLE dot_replacement (
+    id serial8 not null primary key,
+    a text,
+    "a.b" varchar
+);
+
+CREATE INDEX idxdot_replacement ON dot_replacement USING zombodb ((dot_replacement.*)) WITH (dot_replacement='.');
+
+CREATE INDEX idxdot_replacement ON dot_replacement USING zombodb ((dot_replacement.*)) WITH (dot_replacement='_');
+SELECT zdb.index_properties('idxdot_replacement') ? 'a_b' AS has_a_b, zdb.index_properties('idxdot_replacement')->'a'->'properties' IS NULL AS a_not_an_object;
+
+INSERT INTO dot_replacement (a, "a.b") VALUES ('one', 'uno'), ('two', 'dos');
+SELECT id, a, "a.b" FROM dot_replacement WHERE dot_replacement ==> 'a_b:dos' ORDER BY id;
+SELECT id, a, "a.b" FROM dot_replacement WHERE dot_replacement ==> 'a:one' ORDER BY id;
+
+DROP TABLE dot_replacement;
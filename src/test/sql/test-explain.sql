@@ -0,0 +1,12 @@
+CREATE TABLE explain_test (
+    id serial8 not null primary key,
+    title text
+);
+CREATE INDEX idxexplain_test ON explain_test USING zombodb ((explain_test.*));
+INSERT INTO explain_test (title) VALUES ('the quick fox jumped over the lazy fox'), ('a slow dog');
+
+SELECT id, (zdb.explain('idxexplain_test', 'title:fox', ctid)->>'matched')::bool AS matched FROM explain_test ORDER BY id;
+SELECT zdb.explain('idxexplain_test', 'title:fox', ctid)->'explanation' ? 'details' AS has_details FROM explain_test WHERE id = 1;
+SELECT (zdb.explain('idxexplain_test', 'title:fox', '(4294967,1)')->>'matched')::bool AS missing;
+
+DROP TABLE explain_test;
@@ -0,0 +1,18 @@
+CREATE TABLE field_mapping_option (
+    id serial8 not null primary key,
+    title text,
+    sku varchar(32)
+);
+
+CREATE INDEX idxfield_mapping_option ON field_mapping_option USING zombodb ((field_mapping_option.*)) WITH (field_mapping='[]');
+CREATE INDEX idxfield_mapping_option ON field_mapping_option USING zombodb ((field_mapping_option.*)) WITH (field_mapping='{"title": "english"}');
+CREATE INDEX idxfield_mapping_option ON field_mapping_option USING zombodb ((field_mapping_option.*)) WITH (field_mapping='{"nope": {"type": "keyword"}}');
+
+CREATE INDEX idxfield_mapping_option ON field_mapping_option USING zombodb ((field_mapping_option.*)) WITH (field_mapping='{"title": {"type": "text", "analyzer": "english"}, "sku": {"type": "keyword", "index": false}}');
+SELECT (zdb.index_mapping('idxfield_mapping_option')->'mappings'->'doc'->'properties'->'title')::jsonb;
+SELECT (zdb.index_mapping('idxfield_mapping_option')->'mappings'->'doc'->'properties'->'sku')::jsonb;
+
+INSERT INTO field_mapping_option (title, sku) VALUES ('The runners were running', 'ABC-123');
+SELECT id, title FROM field_mapping_option WHERE field_mapping_option ==> 'title:run';
+
+DROP TABLE field_mapping_option;
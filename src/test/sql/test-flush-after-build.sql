@@ -0,0 +1,15 @@
+CREATE TABLE flush_after_build (
+    id serial8 not null primary key,
+    title text
+);
+INSERT INTO flush_after_build (title) VALUES ('one'), ('two');
+SET zdb.log_level TO 'notice';
+
+CREATE INDEX idxflush_after_build ON flush_after_build USING zombodb ((flush_after_build.*));
+DROP INDEX idxflush_after_build;
+
+CREATE INDEX idxflush_after_build ON flush_after_build USING zombodb ((flush_after_build.*)) WITH (flush_after_build=true);
+SELECT id, title FROM flush_after_build WHERE flush_after_build ==> 'title:two' ORDER BY id;
+
+RESET zdb.log_level;
+DROP TABLE flush_after_build;
@@ -0,0 +1,12 @@
+CREATE TABLE get_document (
+    id serial8 not null primary key,
+    title text,
+    tags varchar[]
+);
+CREATE INDEX idxget_document ON get_document USING zombodb ((get_document.*));
+INSERT INTO get_document (title, tags) VALUES ('one', ARRAY['a', 'b']), ('two', NULL);
+
+SELECT id, zdb.get_document('idxget_document', ctid) FROM get_document ORDER BY id;
+SELECT zdb.get_document('idxget_document', '(1000,1)') IS NULL AS missing;
+
+DROP TABLE get_document;
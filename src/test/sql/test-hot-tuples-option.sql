@@ -0,0 +1,18 @@
+CREATE TABLE hot_tuples AS SELECT * FROM events WHERE id IN (1, 2);
+UPDATE hot_tuples SET id = id WHERE id = 1;
+
+CREATE INDEX idxhot_tuples ON hot_tuples USING zombodb ((hot_tuples.*)) WITH (hot_tuples='maybe');
+CREATE INDEX idxhot_tuples ON hot_tuples USING zombodb ((hot_tuples.*)) WITH (hot_tuples='skip');
+SELECT id FROM hot_tuples WHERE hot_tuples ==> 'id:*' ORDER BY id;
+DROP INDEX idxhot_tuples;
+
+CREATE INDEX idxhot_tuples ON hot_tuples USING zombodb ((hot_tuples.*)) WITH (hot_tuples='index');
+SELECT id FROM hot_tuples WHERE hot_tuples ==> 'id:*' ORDER BY id;
+SELECT id FROM hot_tuples WHERE hot_tuples ==> 'id:1' ORDER BY id;
+
+UPDATE hot_tuples SET id = 3 WHERE id = 1;
+SELECT id FROM hot_tuples WHERE hot_tuples ==> 'id:*' ORDER BY id;
+DELETE FROM hot_tuples WHERE id = 3;
+SELECT id FROM hot_tuples WHERE hot_tuples ==> 'id:*' ORDER BY id;
+
+DROP TABLE hot_tuples CASCADE;
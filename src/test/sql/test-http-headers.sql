@@ -0,0 +1,19 @@
+CREATE TABLE http_headers (
+    id serial8 not null primary key,
+    title text
+);
+CREATE INDEX idxhttp_headers ON http_headers USING zombodb ((http_headers.*));
+
+\set VERBOSITY terse
+SET zdb.http_headers TO '["X-Tenant"]';
+SET zdb.http_headers TO '{"X Tenant": "acme"}';
+SET zdb.http_headers TO '{"Content-Type": "text/plain"}';
+SET zdb.http_headers TO '{"X-Tenant": 42}';
+\set VERBOSITY default
+
+SET zdb.http_headers TO '{"X-Tenant": "acme", "X-Opaque-Id": "test-http-headers"}';
+INSERT INTO http_headers (title) VALUES ('one'), ('two');
+SELECT id, title FROM http_headers WHERE http_headers ==> 'title:two' ORDER BY id;
+RESET zdb.http_headers;
+
+DROP TABLE http_headers;
@@ -0,0 +1,19 @@
+CREATE TABLE ignore_above (
+    id serial8 not null primary key,
+    code varchar,
+    sku varchar
+);
+CREATE INDEX idxignore_above ON ignore_above USING zombodb ((ignore_above.*)) WITH (field_mapping='{"sku": {"type": "keyword", "ignore_above": 5}}');
+SELECT (zdb.index_mapping('idxignore_above')->'mappings'->'doc'->'properties'->'code'->'ignore_above')::jsonb;
+SELECT (zdb.index_mapping('idxignore_above')->'mappings'->'doc'->'properties'->'sku'->'ignore_above')::jsonb;
+
+INSERT INTO ignore_above (code, sku) VALUES ('short', 'abc'), (repeat('x', 11000), 'abcdefgh');
+
+-- the oversized values aren't indexed, but their rows are
+SELECT id FROM ignore_above WHERE ignore_above ==> 'code:short' ORDER BY id;
+SELECT id FROM ignore_above WHERE ignore_above ==> 'code:x*' ORDER BY id;
+SELECT id FROM ignore_above WHERE ignore_above ==> 'sku:abc' ORDER BY id;
+SELECT id FROM ignore_above WHERE ignore_above ==> 'sku:abcdefgh' ORDER BY id;
+SELECT id FROM ignore_above WHERE ignore_above ==> 'id:2' ORDER BY id;
+
+DROP TABLE ignore_above;
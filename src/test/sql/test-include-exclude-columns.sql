@@ -0,0 +1,26 @@
+CREATE TABLE include_exclude (
+    id serial8 not null primary key,
+    title text,
+    secret text,
+    notes varchar
+);
+INSERT INTO include_exclude (title, secret, notes) VALUES ('hello', 'hidden', 'note one'), ('world', 'hidden', 'note two');
+
+CREATE INDEX idxinclude_exclude ON include_exclude USING zombodb ((include_exclude.*)) WITH (exclude_columns='nope');
+CREATE INDEX idxinclude_exclude ON include_exclude USING zombodb ((include_exclude.*)) WITH (include_columns='id', exclude_columns='secret');
+CREATE INDEX idxinclude_exclude ON include_exclude USING zombodb ((include_exclude.*)) WITH (exclude_columns='secret');
+SELECT zdb.index_mapping('idxinclude_exclude')->'mappings'->'doc'->'properties'->'secret';
+SELECT id FROM include_exclude WHERE include_exclude ==> 'title:hello' ORDER BY id;
+SELECT id FROM include_exclude WHERE include_exclude ==> 'secret:hidden' ORDER BY id;
+INSERT INTO include_exclude (title, secret, notes) VALUES ('again', 'hidden', 'note three');
+SELECT id FROM include_exclude WHERE include_exclude ==> 'secret:hidden' ORDER BY id;
+SELECT id FROM include_exclude WHERE include_exclude ==> 'notes:"note three"' ORDER BY id;
+
+ALTER INDEX idxinclude_exclude RESET (exclude_columns);
+ALTER INDEX idxinclude_exclude SET (include_columns='id, notes');
+REINDEX INDEX idxinclude_exclude;
+SELECT zdb.index_mapping('idxinclude_exclude')->'mappings'->'doc'->'properties'->'title';
+SELECT id FROM include_exclude WHERE include_exclude ==> 'title:hello' ORDER BY id;
+SELECT id FROM include_exclude WHERE include_exclude ==> 'notes:"note one"' ORDER BY id;
+
+DROP TABLE include_exclude;
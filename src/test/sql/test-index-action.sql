@@ -0,0 +1,22 @@
+CREATE TABLE index_action (
+    id serial8 not null primary key,
+    title text
+);
+CREATE INDEX idxindex_action ON index_action USING zombodb ((index_action.*)) WITH (index_action='upsert');
+CREATE INDEX idxindex_action ON index_action USING zombodb ((index_action.*));
+
+-- (0, 1) is the ctid of the first row we'll insert, which 'index' silently overwrites
+SELECT zdb.request('idxindex_action', 'doc/1', 'PUT', '{"title": "planted"}')::json->>'result';
+INSERT INTO index_action (title) VALUES ('one');
+SELECT * FROM index_action WHERE index_action ==> 'title:one';
+
+-- but 'create' refuses to overwrite (0, 2)
+ALTER INDEX idxindex_action SET (index_action='create');
+SELECT zdb.request('idxindex_action', 'doc/2', 'PUT', '{"title": "planted"}')::json->>'result';
+\set VERBOSITY terse
+INSERT INTO index_action (title) VALUES ('two');
+\set VERBOSITY default
+INSERT INTO index_action (title) VALUES ('three');
+SELECT * FROM index_action WHERE index_action ==> 'title:(one OR three)' ORDER BY id;
+
+DROP TABLE index_action;
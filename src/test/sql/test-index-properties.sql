@@ -0,0 +1,16 @@
+CREATE TABLE index_properties (
+    id serial8 not null primary key,
+    title text,
+    tags varchar
+);
+CREATE INDEX idxindex_properties ON index_properties USING zombodb ((index_properties.*));
+
+SELECT zdb.index_properties('idxindex_properties')->'id';
+SELECT zdb.index_properties('idxindex_properties')->'title'->>'type' AS title_type, zdb.index_properties('idxindex_properties')->'tags'->>'type' AS tags_type;
+SELECT zdb.index_properties('idxindex_properties') ? 'zdb_xmin';
+
+SELECT zdb.request('idxindex_properties', '', 'DELETE');
+\set VERBOSITY terse
+SELECT zdb.index_properties('idxindex_properties');
+
+DROP TABLE index_properties;
@@ -0,0 +1,21 @@
+CREATE TABLE index_settings_option (
+    id serial8 not null primary key,
+    title text
+);
+
+CREATE INDEX idxindex_settings_option ON index_settings_option USING zombodb ((index_settings_option.*)) WITH (index_settings='{"index.codec": "best_compression", "index.max_result_window": 20000}');
+SELECT settings->>'codec' AS codec, settings->>'max_result_window' AS max_result_window, settings->>'number_of_shards' AS shards
+  FROM (SELECT (zdb.request('idxindex_settings_option', '_settings')::json)->zdb.index_name('idxindex_settings_option')->'settings'->'index' AS settings) x;
+
+-- an empty object adds nothing
+ALTER INDEX idxindex_settings_option SET (index_settings='{}');
+REINDEX INDEX idxindex_settings_option;
+SELECT settings->>'codec' AS codec, settings->>'max_result_window' AS max_result_window
+  FROM (SELECT (zdb.request('idxindex_settings_option', '_settings')::json)->zdb.index_name('idxindex_settings_option')->'settings'->'index' AS settings) x;
+
+ALTER INDEX idxindex_settings_option SET (index_settings='["index.codec"]');
+ALTER INDEX idxindex_settings_option SET (index_settings='{"index": {"codec": "best_compression"}}');
+ALTER INDEX idxindex_settings_option SET (index_settings='{"index.number_of_shards": 2}');
+ALTER INDEX idxindex_settings_option SET (index_settings='{"analysis.analyzer.mine.type": "standard"}');
+
+DROP TABLE index_settings_option;
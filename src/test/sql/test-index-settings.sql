@@ -0,0 +1,13 @@
+CREATE TABLE index_settings (
+    id serial8 not null primary key,
+    title text
+);
+
+CREATE INDEX idxindex_settings ON index_settings USING zombodb ((index_settings.*)) WITH (shards=3, replicas=0, refresh_interval='5s');
+SELECT settings->>'number_of_shards' AS shards, settings->>'number_of_replicas' AS replicas, settings->>'refresh_interval' AS refresh_interval
+  FROM (SELECT (zdb.request('idxindex_settings', '_settings')::json)->zdb.index_name('idxindex_settings')->'settings'->'index' AS settings) x;
+
+ALTER INDEX idxindex_settings SET (refresh_interval='-1');
+SELECT (zdb.request('idxindex_settings', '_settings')::json)->zdb.index_name('idxindex_settings')->'settings'->'index'->>'refresh_interval' AS refresh_interval;
+
+DROP TABLE index_settings;
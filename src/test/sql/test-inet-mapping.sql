@@ -0,0 +1,15 @@
+CREATE TABLE inet_mapping (
+    id serial8 not null primary key,
+    addr inet,
+    net cidr
+);
+
+CREATE INDEX idxinet_mapping ON inet_mapping USING zombodb ((inet_mapping.*));
+SELECT (zdb.index_mapping('idxinet_mapping')->'mappings'->'doc'->'properties'->'net')::jsonb;
+
+INSERT INTO inet_mapping (addr, net) VALUES ('10.1.2.3/16', '10.1.0.0/16'), ('192.168.1.5', '192.168.0.0/16'), ('2001:db8::1/64', '2001:db8::/32');
+SELECT id, addr FROM inet_mapping WHERE inet_mapping ==> dsl.term('addr', '10.0.0.0/8') ORDER BY id;
+SELECT id, addr FROM inet_mapping WHERE inet_mapping ==> dsl.term('addr', '2001:db8::1') ORDER BY id;
+SELECT id, net FROM inet_mapping WHERE inet_mapping ==> dsl.term('net', '192.168.44.1') ORDER BY id;
+
+DROP TABLE inet_mapping;
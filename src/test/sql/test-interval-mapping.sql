@@ -0,0 +1,33 @@
+CREATE TABLE interval_mapping (
+    id serial8 not null primary key,
+    dur interval,
+    durs interval[]
+);
+
+CREATE INDEX idxinterval_mapping ON interval_mapping USING zombodb ((interval_mapping.*));
+SELECT (zdb.index_mapping('idxinterval_mapping')->'mappings'->'doc'->'properties'->'dur')::jsonb;
+
+-- a month is 30 days, a year 365.25 days, and fractional seconds are truncated
+INSERT INTO interval_mapping (dur, durs) VALUES ('90 seconds', '{1 minute,1 hour}'), ('1 day 02:00:00.75', NULL), ('1 mon', NULL), ('1 year', NULL), ('-5 minutes', NULL);
+SELECT id, dur, zdb.get_document('idxinterval_mapping', ctid)->'dur' AS indexed FROM interval_mapping ORDER BY id;
+SELECT id, dur FROM interval_mapping WHERE interval_mapping ==> dsl.range(field=>'dur', gte=>'86400') ORDER BY id;
+SELECT id, durs FROM interval_mapping WHERE interval_mapping ==> dsl.term('durs', '3600') ORDER BY id;
+
+-- ISO-8601 durations instead, for a domain over interval
+CREATE DOMAIN iso_interval AS interval;
+SELECT zdb.define_type_conversion('iso_interval'::regtype, 'zdb.interval_to_iso8601_json'::regproc);
+SELECT zdb.define_type_mapping('iso_interval'::regtype, '{"type": "keyword"}'::json);
+CREATE TABLE iso_interval_mapping (
+    id serial8 not null primary key,
+    dur iso_interval
+);
+CREATE INDEX idxiso_interval_mapping ON iso_interval_mapping USING zombodb ((iso_interval_mapping.*));
+INSERT INTO iso_interval_mapping (dur) VALUES ('1 year 2 mons 3 days 04:05:06.5'), ('-5 minutes'), ('0');
+SELECT id, zdb.get_document('idxiso_interval_mapping', ctid)->>'dur' AS indexed, (zdb.get_document('idxiso_interval_mapping', ctid)->>'dur')::interval = dur AS round_trips FROM iso_interval_mapping ORDER BY id;
+SELECT id, dur FROM iso_interval_mapping WHERE iso_interval_mapping ==> dsl.term('dur', 'PT-5M') ORDER BY id;
+
+DROP TABLE interval_mapping;
+DROP TABLE iso_interval_mapping;
+DELETE FROM zdb.type_conversions WHERE typeoid = 'iso_interval'::regtype;
+DELETE FROM zdb.type_mappings WHERE type_name = 'iso_interval'::regtype;
+DROP DOMAIN iso_interval;
@@ -0,0 +1,20 @@
+CREATE TABLE llapi_bulk (
+    id serial8,
+    title text
+);
+CREATE INDEX idxllapi_bulk ON llapi_bulk USING zombodb ((llapi_bulk.*));
+SELECT zdb.bulk_insert('idxllapi_bulk', ARRAY['{"title":"this will ERROR"}'::jsonb]);
+
+ALTER INDEX idxllapi_bulk SET (llapi=true);
+SELECT zdb.bulk_insert('idxllapi_bulk', ARRAY['{"title":"one"}'::jsonb, '[1, 2]'::jsonb]);
+SELECT zdb.bulk_insert('idxllapi_bulk', ARRAY['{"title":"one"}'::jsonb, NULL]);
+SELECT zdb.bulk_insert('idxllapi_bulk', ARRAY['{"id":1,"title":"one"}', '{"id":2,"title":"two"}', '{"id":3,"title":"three"}']::jsonb[]);
+
+BEGIN;
+SELECT zdb.bulk_insert('idxllapi_bulk', ARRAY['{"id":4,"title":"four - aborted"}']::jsonb[]);
+ABORT;
+
+SELECT * FROM zdb.terms('idxllapi_bulk', 'title', dsl.match_all()) ORDER BY term;
+SELECT zdb.count('idxllapi_bulk', dsl.match_all());
+
+DROP TABLE llapi_bulk CASCADE;
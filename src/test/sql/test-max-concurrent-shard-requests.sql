@@ -0,0 +1,22 @@
+CREATE TABLE max_concurrent_shard_requests (
+    id serial8 not null primary key,
+    title text
+);
+
+CREATE INDEX idxmax_concurrent_shard_requests ON max_concurrent_shard_requests USING zombodb ((max_concurrent_shard_requests.*)) WITH (max_concurrent_shard_requests=-1);
+
+CREATE INDEX idxmax_concurrent_shard_requests ON max_concurrent_shard_requests USING zombodb ((max_concurrent_shard_requests.*)) WITH (shards=5, max_concurrent_shard_requests=1, alias='max_concurrent_shard_requests_alias');
+INSERT INTO max_concurrent_shard_requests (title) SELECT 'row ' || x FROM generate_series(1, 100) x;
+SELECT count(*) FROM max_concurrent_shard_requests WHERE max_concurrent_shard_requests ==> 'title:row';
+-- the url of every search is logged at DEBUG1
+SET client_min_messages TO 'debug1';
+\set VERBOSITY terse
+SELECT * FROM zdb.terms('idxmax_concurrent_shard_requests', 'title', 'title:1') ORDER BY term;
+
+ALTER INDEX idxmax_concurrent_shard_requests SET (max_concurrent_shard_requests=0);
+SELECT * FROM zdb.terms('idxmax_concurrent_shard_requests', 'title', 'title:1') ORDER BY term;
+\set VERBOSITY default
+RESET client_min_messages;
+SELECT count(*) FROM max_concurrent_shard_requests WHERE max_concurrent_shard_requests ==> 'title:row';
+
+DROP TABLE max_concurrent_shard_requests;
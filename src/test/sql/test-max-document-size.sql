@@ -0,0 +1,16 @@
+CREATE TABLE max_document_size (
+    id serial8 not null primary key,
+    body text
+);
+INSERT INTO max_document_size (body) VALUES ('small'), (repeat('large ', 1000)), ('small too');
+
+CREATE INDEX idxmax_document_size ON max_document_size USING zombodb ((max_document_size.*)) WITH (on_error='skip', max_document_size=1024);
+SELECT ctid, reason LIKE 'document_too_large:%' AS too_large FROM zdb.dead_letters WHERE index = 'idxmax_document_size'::regclass;
+SELECT id, body FROM max_document_size WHERE max_document_size ==> 'small' ORDER BY id;
+
+INSERT INTO max_document_size (body) VALUES (repeat('larger ', 1000)), ('small again');
+SELECT ctid FROM zdb.dead_letters WHERE index = 'idxmax_document_size'::regclass ORDER BY ctid;
+SELECT id, body FROM max_document_size WHERE max_document_size ==> 'small' ORDER BY id;
+
+DELETE FROM zdb.dead_letters WHERE index = 'idxmax_document_size'::regclass;
+DROP TABLE max_document_size;
@@ -0,0 +1,20 @@
+CREATE TABLE metrics (
+    id serial8 not null primary key,
+    title text
+);
+CREATE INDEX idxmetrics ON metrics USING zombodb ((metrics.*));
+INSERT INTO metrics (title) SELECT 'row ' || x FROM generate_series(1, 10) x;
+
+SELECT line FROM regexp_split_to_table(zdb.metrics(), E'\n') AS line WHERE line LIKE '#%';
+SELECT regexp_replace(line, ' [^ ]+$', '') AS sample FROM regexp_split_to_table(zdb.metrics(), E'\n') AS line WHERE line LIKE '%"idxmetrics"%';
+
+-- every sample is a metric name, its labels, and a number
+SELECT count(*) FROM regexp_split_to_table(zdb.metrics(), E'\n') AS line
+ WHERE line <> '' AND line NOT LIKE '#%' AND line !~ '^[a-z_]+\{index="[^"]+"(,quantile="[0-9.]+")?\} [0-9.e+-]+$';
+
+-- every session's work is in the shared stats, which is what metrics() reports
+SELECT shared.requests = mine.requests AND shared.documents = mine.documents AND shared.latency_count = mine.latency_count
+  FROM zdb.bulk_stats(shared => true) shared, zdb.bulk_stats() mine
+ WHERE shared.index = 'idxmetrics'::regclass AND mine.index = 'idxmetrics'::regclass;
+
+DROP TABLE metrics;
@@ -0,0 +1,23 @@
+CREATE TABLE multiple_urls (
+    id serial8 not null primary key,
+    title text
+);
+
+CREATE INDEX idxmultiple_urls ON multiple_urls USING zombodb ((multiple_urls.*)) WITH (url='http://localhost:9200/,http://localhost:9201');
+CREATE INDEX idxmultiple_urls ON multiple_urls USING zombodb ((multiple_urls.*)) WITH (url='http://localhost:9200/,');
+
+-- nothing listens on port 1, so every request that would have gone there fails over to the real cluster
+SET client_min_messages = error;
+DO $$
+BEGIN
+    EXECUTE format('CREATE INDEX idxmultiple_urls ON multiple_urls USING zombodb ((multiple_urls.*)) WITH (url=%L)',
+                   'http://localhost:1/,' || current_setting('zdb.default_elasticsearch_url'));
+END;
+$$;
+INSERT INTO multiple_urls (title) SELECT 'title ' || x FROM generate_series(1, 10) x;
+SELECT count(*) FROM multiple_urls WHERE multiple_urls ==> 'title:title';
+UPDATE multiple_urls SET title = 'updated' WHERE id = 5;
+SELECT id, title FROM multiple_urls WHERE multiple_urls ==> 'title:updated' ORDER BY id;
+RESET client_min_messages;
+
+DROP TABLE multiple_urls;
@@ -0,0 +1,30 @@
+CREATE TABLE nonfinite_floats (
+    id serial8 not null primary key,
+    r real,
+    d double precision,
+    arr double precision[]
+);
+CREATE INDEX idxnonfinite_floats ON nonfinite_floats USING zombodb ((nonfinite_floats.*));
+
+-- the default, 'null'
+INSERT INTO nonfinite_floats (r, d, arr) VALUES ('NaN', 'Infinity', '{1.5,-Infinity}');
+SELECT id FROM nonfinite_floats WHERE nonfinite_floats ==> dsl.field_missing('r') ORDER BY id;
+SELECT id FROM nonfinite_floats WHERE nonfinite_floats ==> dsl.field_missing('d') ORDER BY id;
+SELECT id FROM nonfinite_floats WHERE nonfinite_floats ==> dsl.term('arr', '1.5') ORDER BY id;
+
+ALTER INDEX idxnonfinite_floats SET (nonfinite_floats='clamp');
+INSERT INTO nonfinite_floats (r, d, arr) VALUES ('-Infinity', 'Infinity', '{NaN,Infinity}');
+SELECT id FROM nonfinite_floats WHERE nonfinite_floats ==> dsl.range(field=>'r', lte=>'-3e38') ORDER BY id;
+SELECT id FROM nonfinite_floats WHERE nonfinite_floats ==> dsl.range(field=>'d', gte=>'1e308') ORDER BY id;
+SELECT id FROM nonfinite_floats WHERE nonfinite_floats ==> dsl.range(field=>'arr', gte=>'1e308') ORDER BY id;
+
+ALTER INDEX idxnonfinite_floats SET (nonfinite_floats='error');
+\set VERBOSITY terse
+INSERT INTO nonfinite_floats (r, d, arr) VALUES (1.0, 'NaN', NULL);
+INSERT INTO nonfinite_floats (r, d, arr) VALUES (1.0, 2.0, '{3.0,-Infinity}');
+ALTER INDEX idxnonfinite_floats SET (nonfinite_floats='sometimes');
+\set VERBOSITY default
+INSERT INTO nonfinite_floats (r, d, arr) VALUES (1.0, 2.0, '{3.0,4.0}');
+SELECT id, r, d, arr FROM nonfinite_floats WHERE nonfinite_floats ==> 'r:1' ORDER BY id;
+
+DROP TABLE nonfinite_floats;
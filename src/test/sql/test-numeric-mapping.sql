@@ -0,0 +1,25 @@
+CREATE TABLE numeric_mapping (
+    id serial8 not null primary key,
+    amount numeric(12, 4),
+    big numeric,
+    wide numeric(18, 2)
+);
+
+CREATE INDEX idxnumeric_mapping ON numeric_mapping USING zombodb ((numeric_mapping.*));
+SELECT (zdb.index_mapping('idxnumeric_mapping')->'mappings'->'doc'->'properties'->'amount')::jsonb;
+SELECT (zdb.index_mapping('idxnumeric_mapping')->'mappings'->'doc'->'properties'->'big')::jsonb;
+SELECT (zdb.index_mapping('idxnumeric_mapping')->'mappings'->'doc'->'properties'->'wide')::jsonb;
+
+INSERT INTO numeric_mapping (amount, big) VALUES (12345678.1234, 3.14159265358979323846264338327950288);
+INSERT INTO numeric_mapping (amount, big) VALUES (0.0001, 'NaN');
+SELECT id, amount, big FROM numeric_mapping WHERE numeric_mapping ==> 'amount:12345678.1234' ORDER BY id;
+SELECT id, amount, big FROM numeric_mapping WHERE numeric_mapping ==> 'big.exact:"3.14159265358979323846264338327950288"' ORDER BY id;
+SELECT id, amount, big FROM numeric_mapping WHERE numeric_mapping ==> 'amount:0.0001 AND NOT big:*' ORDER BY id;
+
+-- wide and unconstrained numerics still compare as numbers
+INSERT INTO numeric_mapping (amount, big, wide) VALUES (1, 9, 9), (1, 150, 150.25);
+SELECT id, big, wide FROM numeric_mapping WHERE numeric_mapping ==> 'big:>100' ORDER BY id;
+SELECT id, big, wide FROM numeric_mapping WHERE numeric_mapping ==> 'wide:[10 TO 200]' ORDER BY id;
+SELECT zdb.sum('idxnumeric_mapping', 'big', 'big:>5') AS big, zdb.sum('idxnumeric_mapping', 'wide', 'wide:*') AS wide;
+
+DROP TABLE numeric_mapping;
@@ -0,0 +1,27 @@
+CREATE TABLE on_error_skip (
+    id serial8 not null primary key,
+    qty text
+);
+INSERT INTO on_error_skip (qty) VALUES ('1'), ('two'), ('3');
+
+CREATE INDEX idxon_error_skip ON on_error_skip USING zombodb ((on_error_skip.*)) WITH (on_error='nope');
+CREATE INDEX idxon_error_skip ON on_error_skip USING zombodb ((on_error_skip.*)) WITH (on_error='skip', field_mapping='{"qty": {"type": "integer"}}');
+SELECT ctid, reason LIKE 'mapper_parsing_exception:%' AS mapping_error FROM zdb.dead_letters WHERE index = 'idxon_error_skip'::regclass;
+SELECT id, qty FROM on_error_skip WHERE on_error_skip ==> 'qty:3';
+
+INSERT INTO on_error_skip (qty) VALUES ('five');
+SELECT ctid FROM zdb.dead_letters WHERE index = 'idxon_error_skip'::regclass ORDER BY ctid;
+
+-- a failed change to an existing document's visibility is never skipped, or the old row would stay visible
+SELECT (zdb.request('idxon_error_skip', '_delete_by_query?refresh=true', 'POST', '{"query": {"term": {"qty": 3}}}')::json)->>'deleted' AS deleted;
+DO LANGUAGE plpgsql $$
+BEGIN
+    UPDATE on_error_skip SET qty = '33' WHERE id = 3;
+    PERFORM count(*) FROM on_error_skip WHERE on_error_skip ==> 'qty:33';
+EXCEPTION WHEN io_error THEN
+    RAISE NOTICE 'the update wasn''t skipped: %', SQLSTATE;
+END;
+$$;
+
+DELETE FROM zdb.dead_letters WHERE index = 'idxon_error_skip'::regclass;
+DROP TABLE on_error_skip;
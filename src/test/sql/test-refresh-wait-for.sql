@@ -0,0 +1,18 @@
+CREATE TABLE refresh_wait_for (
+    id serial8 not null primary key,
+    title text
+);
+
+CREATE INDEX idxrefresh_wait_for ON refresh_wait_for USING zombodb ((refresh_wait_for.*)) WITH (refresh_interval='wait_for');
+SELECT (zdb.request('idxrefresh_wait_for', '_settings')::json)->zdb.index_name('idxrefresh_wait_for')->'settings'->'index'->>'refresh_interval' AS refresh_interval;
+
+INSERT INTO refresh_wait_for (title) VALUES ('one'), ('two');
+SELECT id, title FROM refresh_wait_for WHERE refresh_wait_for ==> 'title:two' ORDER BY id;
+
+UPDATE refresh_wait_for SET title = 'three' WHERE id = 1;
+SELECT id, title FROM refresh_wait_for WHERE refresh_wait_for ==> 'title:three' ORDER BY id;
+
+ALTER INDEX idxrefresh_wait_for SET (refresh_interval='-1');
+SELECT (zdb.request('idxrefresh_wait_for', '_settings')::json)->zdb.index_name('idxrefresh_wait_for')->'settings'->'index'->>'refresh_interval' AS refresh_interval;
+
+DROP TABLE refresh_wait_for;
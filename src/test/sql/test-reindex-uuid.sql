@@ -0,0 +1,15 @@
+CREATE TABLE reindex_uuid (
+    id serial8 not null primary key,
+    title text
+);
+INSERT INTO reindex_uuid (title) VALUES ('one'), ('two');
+
+-- the named Elasticsearch index doesn't exist yet, and then does when we REINDEX
+CREATE INDEX idxreindex_uuid ON reindex_uuid USING zombodb ((reindex_uuid.*)) WITH (uuid='zdb_test_reindex_uuid');
+SELECT id FROM reindex_uuid WHERE reindex_uuid ==> 'title:two' ORDER BY id;
+INSERT INTO reindex_uuid (title) VALUES ('two again');
+REINDEX INDEX idxreindex_uuid;
+SELECT id FROM reindex_uuid WHERE reindex_uuid ==> 'title:two' ORDER BY id;
+SELECT zdb.count('idxreindex_uuid', dsl.match_all());
+
+DROP TABLE reindex_uuid;
@@ -0,0 +1,23 @@
+CREATE TABLE routing_option (
+    id serial8 not null primary key,
+    tenant text,
+    title text
+);
+INSERT INTO routing_option (tenant, title) VALUES ('acme', 'one');
+
+CREATE INDEX idxrouting_option ON routing_option USING zombodb ((routing_option.*)) WITH (routing='nope');
+CREATE INDEX idxrouting_option ON routing_option USING zombodb ((routing_option.*)) WITH (routing='tenant', shards=8);
+
+INSERT INTO routing_option (tenant, title) VALUES ('acme', 'two'), ('globex', 'three'), (NULL, 'four');
+SELECT title, (zdb.request('idxrouting_option', '_search?filter_path=hits.hits._routing&q=title:' || title)::json)->'hits'->'hits'->0->>'_routing' AS routing FROM routing_option ORDER BY id;
+
+UPDATE routing_option SET title = 'deux' WHERE title = 'two';
+SELECT title FROM routing_option WHERE routing_option ==> 'tenant:acme' ORDER BY id;
+
+-- requests for a single document must be routed to its shard
+SELECT title, zdb.get_document('idxrouting_option', ctid)->>'title' AS document, (zdb.explain('idxrouting_option', ('title:' || title)::zdbquery, ctid)->>'matched')::boolean AS matched, zdb.term_vectors('idxrouting_option', ctid, 'title') IS NOT NULL AS has_term_vectors FROM routing_option ORDER BY id;
+
+VACUUM routing_option;
+SELECT zdb.raw_count('idxrouting_option', 'tenant:acme');
+
+DROP TABLE routing_option;
@@ -0,0 +1,14 @@
+CREATE TABLE scroll_size (
+    id serial8 not null primary key,
+    title text
+);
+INSERT INTO scroll_size (title) SELECT 'row ' || x FROM generate_series(1, 25) x;
+CREATE INDEX idxscroll_size ON scroll_size USING zombodb ((scroll_size.*));
+
+SET zdb.scroll_size TO 3;
+SELECT count(*) FROM scroll_size WHERE scroll_size ==> 'title:row';
+SELECT id FROM scroll_size WHERE scroll_size ==> 'title:row' ORDER BY id LIMIT 5;
+SET zdb.scroll_size TO 0;
+RESET zdb.scroll_size;
+
+DROP TABLE scroll_size;
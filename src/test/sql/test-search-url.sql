@@ -0,0 +1,23 @@
+CREATE TABLE search_url (
+    id serial8 not null primary key,
+    title text
+);
+CREATE INDEX idxsearch_url ON search_url USING zombodb ((search_url.*)) WITH (search_url='http://localhost:9200');
+
+DO $$
+BEGIN
+    EXECUTE format('CREATE INDEX idxsearch_url ON search_url USING zombodb ((search_url.*)) WITH (search_url=%L)',
+                   current_setting('zdb.default_elasticsearch_url'));
+END;
+$$;
+INSERT INTO search_url (title) VALUES ('one'), ('two');
+SELECT id, title FROM search_url WHERE search_url ==> 'title:two' ORDER BY id;
+SELECT zdb.count('idxsearch_url', 'title:*');
+
+-- nothing listens on port 1, but writes don't use the 'search_url'
+ALTER INDEX idxsearch_url SET (search_url='http://localhost:1/');
+INSERT INTO search_url (title) VALUES ('three');
+ALTER INDEX idxsearch_url RESET (search_url);
+SELECT id, title FROM search_url WHERE search_url ==> 'title:three' ORDER BY id;
+
+DROP TABLE search_url;
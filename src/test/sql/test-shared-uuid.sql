@@ -0,0 +1,18 @@
+CREATE TABLE shared_uuid_a (
+    id serial8 not null primary key,
+    title text
+);
+CREATE TABLE shared_uuid_b (
+    id serial8 not null primary key,
+    title text
+);
+
+CREATE INDEX idxshared_uuid_a ON shared_uuid_a USING zombodb ((shared_uuid_a.*)) WITH (uuid='zdb_test_shared_uuid');
+SELECT zdb.index_name('idxshared_uuid_a');
+
+\set VERBOSITY terse
+CREATE INDEX idxshared_uuid_b ON shared_uuid_b USING zombodb ((shared_uuid_b.*)) WITH (uuid='zdb_test_shared_uuid');
+\set VERBOSITY default
+
+DROP TABLE shared_uuid_a;
+DROP TABLE shared_uuid_b;
@@ -0,0 +1,30 @@
+CREATE TABLE single_document_bulk (
+    id serial8 not null primary key,
+    qty varchar
+);
+CREATE INDEX idxsingle_document_bulk ON single_document_bulk USING zombodb ((single_document_bulk.*)) WITH (field_mapping='{"qty": {"type": "integer"}}');
+
+-- a one-row statement sends its _bulk request without going through curl's multi interface
+INSERT INTO single_document_bulk (qty) VALUES ('1');
+SELECT id, qty FROM single_document_bulk WHERE single_document_bulk ==> 'qty:1' ORDER BY id;
+UPDATE single_document_bulk SET qty = '2' WHERE id = 1;
+SELECT id, qty FROM single_document_bulk WHERE single_document_bulk ==> 'qty:1 OR qty:2' ORDER BY id;
+
+-- and its errors are reported the same way
+DO LANGUAGE plpgsql $$
+BEGIN
+    INSERT INTO single_document_bulk (qty) VALUES ('three');
+    PERFORM count(*) FROM single_document_bulk WHERE single_document_bulk ==> 'qty:3';
+EXCEPTION WHEN data_exception THEN
+    RAISE NOTICE 'Elasticsearch couldn''t parse the document: %', SQLSTATE;
+END;
+$$;
+
+-- or skipped, with on_error='skip'
+ALTER INDEX idxsingle_document_bulk SET (on_error='skip');
+INSERT INTO single_document_bulk (qty) VALUES ('four');
+SELECT reason LIKE 'mapper_parsing_exception:%' AS mapping_error FROM zdb.dead_letters WHERE index = 'idxsingle_document_bulk'::regclass;
+SELECT id, qty FROM single_document_bulk WHERE single_document_bulk ==> 'qty:*' ORDER BY id;
+
+DELETE FROM zdb.dead_letters WHERE index = 'idxsingle_document_bulk'::regclass;
+DROP TABLE single_document_bulk;
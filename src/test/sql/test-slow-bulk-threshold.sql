@@ -0,0 +1,25 @@
+SHOW zdb.slow_bulk_threshold;
+SET zdb.slow_bulk_threshold TO '2s';
+SHOW zdb.slow_bulk_threshold;
+SET zdb.slow_bulk_threshold TO -1;
+SET zdb.slow_bulk_threshold TO 0;
+SHOW zdb.slow_bulk_threshold;
+RESET zdb.slow_bulk_threshold;
+
+-- a round trip to Elasticsearch takes at least a millisecond, so this is logged.  Its timing and url are in the DETAIL, which terse hides.
+-- besides the row, the request marks the transaction in progress and then committed
+CREATE TABLE slow_bulk_threshold (
+    id serial8 not null primary key,
+    title text
+);
+CREATE INDEX idxslow_bulk_threshold ON slow_bulk_threshold USING zombodb ((slow_bulk_threshold.*));
+SET zdb.slow_bulk_threshold TO 1;
+SET zdb.log_level TO 'log';
+SET client_min_messages TO 'log';
+\set VERBOSITY terse
+INSERT INTO slow_bulk_threshold (title) VALUES ('one');
+\set VERBOSITY default
+RESET client_min_messages;
+RESET zdb.log_level;
+RESET zdb.slow_bulk_threshold;
+DROP TABLE slow_bulk_threshold;
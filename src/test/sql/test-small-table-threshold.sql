@@ -0,0 +1,27 @@
+CREATE TABLE small_table_threshold (
+    id serial8 not null primary key,
+    title text
+);
+INSERT INTO small_table_threshold (title) VALUES ('one'), ('two');
+SET zdb.log_level TO 'notice';
+SET zdb.bulk_concurrency_per_node TO 16;
+SET maintenance_work_mem TO '8MB';
+
+-- a table this small is built one _bulk request at a time, which always fits
+CREATE INDEX idxsmall_table_threshold ON small_table_threshold USING zombodb ((small_table_threshold.*)) WITH (batch_size=2097152, bulk_concurrency=0);
+SELECT id, title FROM small_table_threshold WHERE small_table_threshold ==> 'title:two' ORDER BY id;
+DROP INDEX idxsmall_table_threshold;
+
+-- but an explicit bulk_concurrency is used as it is
+CREATE INDEX idxsmall_table_threshold ON small_table_threshold USING zombodb ((small_table_threshold.*)) WITH (batch_size=2097152, bulk_concurrency=12);
+DROP INDEX idxsmall_table_threshold;
+
+SET zdb.small_table_threshold TO 0;
+CREATE INDEX idxsmall_table_threshold ON small_table_threshold USING zombodb ((small_table_threshold.*)) WITH (batch_size=2097152, bulk_concurrency=0);
+SELECT id, title FROM small_table_threshold WHERE small_table_threshold ==> 'title:two' ORDER BY id;
+
+RESET zdb.small_table_threshold;
+RESET maintenance_work_mem;
+RESET zdb.bulk_concurrency_per_node;
+RESET zdb.log_level;
+DROP TABLE small_table_threshold;
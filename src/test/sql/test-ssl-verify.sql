@@ -0,0 +1,13 @@
+CREATE TABLE ssl_verify (
+    id serial8 not null primary key,
+    title text
+);
+CREATE INDEX idxssl_verify ON ssl_verify USING zombodb ((ssl_verify.*));
+
+SHOW zdb.ssl_verify;
+SET zdb.ssl_verify TO off;
+INSERT INTO ssl_verify (title) VALUES ('one'), ('two');
+SELECT id, title FROM ssl_verify WHERE ssl_verify ==> 'title:two' ORDER BY id;
+RESET zdb.ssl_verify;
+
+DROP TABLE ssl_verify;
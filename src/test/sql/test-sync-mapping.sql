@@ -0,0 +1,18 @@
+CREATE TABLE sync_mapping (
+    id serial8 not null primary key,
+    first_name varchar(25),
+    last_name varchar(64)
+);
+CREATE INDEX idxsync_mapping ON sync_mapping USING zombodb ((sync_mapping.*));
+
+SELECT zdb.define_es_only_field('sync_mapping', 'full_name', '{"type":"text", "analyzer":"standard"}');
+SELECT (zdb.index_mapping('idxsync_mapping')->'mappings'->'doc'->'properties')::jsonb ? 'full_name' AS has_full_name;
+SELECT zdb.sync_mapping('idxsync_mapping');
+SELECT (zdb.index_mapping('idxsync_mapping')->'mappings'->'doc'->'properties')::jsonb ? 'full_name' AS has_full_name;
+
+SELECT zdb.define_field_mapping('sync_mapping', 'first_name', '{"type":"long"}');
+\set VERBOSITY terse
+SELECT zdb.sync_mapping('idxsync_mapping');
+\set VERBOSITY default
+
+DROP TABLE sync_mapping;
@@ -0,0 +1,19 @@
+SELECT zdb.define_filter('test_synonyms', '{"type": "synonym", "synonyms": ["car, automobile", "tv => television"]}');
+SELECT zdb.define_filter('test_stopwords', '{"type": "stop", "stopwords": ["the", "a", "an"]}');
+SELECT zdb.define_analyzer('test_synonyms_search', '{"tokenizer": "standard", "filter": ["lowercase", "test_stopwords", "test_synonyms"]}');
+
+CREATE TABLE synonyms (
+    id serial8 not null primary key,
+    title text
+);
+CREATE INDEX idxsynonyms ON synonyms USING zombodb ((synonyms.*)) WITH (default_analyzer='test_synonyms_search');
+SELECT (zdb.index_mapping('idxsynonyms')->'mappings'->'doc'->'properties'->'title'->>'analyzer') AS analyzer;
+
+INSERT INTO synonyms (title) VALUES ('the car'), ('a new tv'), ('the end');
+SELECT id, title FROM synonyms WHERE synonyms ==> 'title:automobile' ORDER BY id;
+SELECT id, title FROM synonyms WHERE synonyms ==> 'title:television' ORDER BY id;
+SELECT id, title FROM synonyms WHERE synonyms ==> 'title:the' ORDER BY id;
+
+DROP TABLE synonyms;
+DELETE FROM zdb.analyzers WHERE name = 'test_synonyms_search';
+DELETE FROM zdb.filters WHERE name IN ('test_synonyms', 'test_stopwords');
@@ -0,0 +1,17 @@
+CREATE TABLE term_vectors (
+    id serial8 not null primary key,
+    title text,
+    "sub title" text
+);
+CREATE INDEX idxterm_vectors ON term_vectors USING zombodb ((term_vectors.*));
+INSERT INTO term_vectors (title, "sub title") VALUES ('the quick fox jumped over the lazy fox', 'slow dog'), (NULL, NULL);
+
+SELECT jsonb_object_keys(zdb.term_vectors('idxterm_vectors', ctid, 'title')->'terms') AS term FROM term_vectors WHERE id = 1 ORDER BY 1;
+SELECT zdb.term_vectors('idxterm_vectors', ctid, 'title')->'terms'->'fox'->'term_freq' AS fox_freq FROM term_vectors WHERE id = 1;
+SELECT zdb.term_vectors('idxterm_vectors', ctid, 'title') IS NULL AS no_terms FROM term_vectors WHERE id = 2;
+SELECT zdb.term_vectors('idxterm_vectors', '(4294967,1)', 'title') IS NULL AS missing;
+
+-- the field name is escaped in the request's url
+SELECT jsonb_object_keys(zdb.term_vectors('idxterm_vectors', ctid, 'sub title')->'terms') AS term FROM term_vectors WHERE id = 1 ORDER BY 1;
+
+DROP TABLE term_vectors;
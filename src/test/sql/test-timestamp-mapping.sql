@@ -0,0 +1,22 @@
+SET timezone TO 'America/New_York';
+CREATE TABLE timestamp_mapping (
+    id serial8 not null primary key,
+    d date,
+    ts timestamp,
+    tstz timestamptz
+);
+INSERT INTO timestamp_mapping (d, ts, tstz) VALUES
+    ('2020-06-15', '2020-06-15 12:34:56.789', '2020-06-15 12:34:56.789-04'),
+    ('infinity', 'infinity', 'infinity'),
+    ('-infinity', '-infinity', '-infinity');
+CREATE INDEX idxtimestamp_mapping ON timestamp_mapping USING zombodb ((timestamp_mapping.*));
+
+SELECT (zdb.index_mapping('idxtimestamp_mapping')->'mappings'->'doc'->'properties'->'tstz')::jsonb;
+
+SELECT id FROM timestamp_mapping WHERE timestamp_mapping ==> dsl.range(field=>'d', gte=>'2020-06-15', lt=>'2020-06-16') ORDER BY id;
+SELECT id FROM timestamp_mapping WHERE timestamp_mapping ==> dsl.range(field=>'ts', gte=>'2020-06-15T12:00:00', lt=>'2020-06-15T13:00:00') ORDER BY id;
+SELECT id FROM timestamp_mapping WHERE timestamp_mapping ==> dsl.range(field=>'tstz', gte=>'2020-06-15T16:34:56.789Z', lte=>'2020-06-15T16:34:56.789Z') ORDER BY id;
+SELECT id FROM timestamp_mapping WHERE timestamp_mapping ==> dsl.not(dsl.field_exists('tstz')) ORDER BY id;
+
+DROP TABLE timestamp_mapping;
+RESET timezone;
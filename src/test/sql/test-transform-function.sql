@@ -0,0 +1,46 @@
+CREATE TABLE transform_function (
+    id serial8 not null primary key,
+    name text,
+    ssn varchar
+);
+CREATE FUNCTION transform_function_redact(doc jsonb) RETURNS jsonb IMMUTABLE LANGUAGE sql AS $$
+    SELECT doc - 'ssn';
+$$;
+CREATE FUNCTION transform_function_wrong(doc jsonb) RETURNS json IMMUTABLE LANGUAGE sql AS $$
+    SELECT doc::json;
+$$;
+CREATE FUNCTION transform_function_empty(doc jsonb) RETURNS jsonb IMMUTABLE LANGUAGE sql AS $$
+    SELECT '{}'::jsonb;
+$$;
+
+CREATE INDEX idxtransform_function ON transform_function USING zombodb ((transform_function.*)) WITH (transform_function='transform_function_nope');
+CREATE INDEX idxtransform_function ON transform_function USING zombodb ((transform_function.*)) WITH (transform_function='transform_function_wrong');
+
+INSERT INTO transform_function (name, ssn) VALUES ('one', '123-45-6789');
+CREATE INDEX idxtransform_function ON transform_function USING zombodb ((transform_function.*)) WITH (transform_function='transform_function_redact');
+INSERT INTO transform_function (name, ssn) VALUES ('two', '987-65-4321');
+SELECT id, zdb.get_document('idxtransform_function', ctid) FROM transform_function ORDER BY id;
+SELECT id, name FROM transform_function WHERE transform_function ==> 'name:two' ORDER BY id;
+SELECT id, name FROM transform_function WHERE transform_function ==> 'ssn:"987-65-4321"' ORDER BY id;
+
+
+-- the function is resolved against the search_path once, when the option is set, and stored schema-qualified
+CREATE SCHEMA transform_function_schema;
+CREATE FUNCTION transform_function_schema.transform_function_upper(doc jsonb) RETURNS jsonb IMMUTABLE LANGUAGE sql AS $$
+    SELECT doc - 'ssn' || jsonb_build_object('name', upper(doc->>'name'));
+$$;
+SET search_path TO transform_function_schema, public;
+ALTER INDEX idxtransform_function SET (transform_function='transform_function_upper');
+RESET search_path;
+SELECT option_value FROM pg_options_to_table((SELECT reloptions FROM pg_class WHERE oid = 'idxtransform_function'::regclass)) WHERE option_name = 'transform_function';
+INSERT INTO transform_function (name, ssn) VALUES ('three', '555-55-5555');
+SELECT id, zdb.get_document('idxtransform_function', ctid) FROM transform_function WHERE id = 3;
+
+ALTER INDEX idxtransform_function SET (transform_function='transform_function_empty');
+INSERT INTO transform_function (name, ssn) VALUES ('four', '444-44-4444');
+
+DROP TABLE transform_function;
+DROP FUNCTION transform_function_redact;
+DROP FUNCTION transform_function_wrong;
+DROP FUNCTION transform_function_empty;
+DROP SCHEMA transform_function_schema CASCADE;
@@ -0,0 +1,29 @@
+CREATE TABLE update_script (
+    id serial8 not null primary key,
+    title text
+);
+
+-- the script is trusted input, so only a superuser can set it
+DROP ROLE IF EXISTS zdb_update_script;
+CREATE ROLE zdb_update_script;
+CREATE TABLE update_script_unprivileged (
+    id serial8 not null primary key,
+    title text
+);
+ALTER TABLE update_script_unprivileged OWNER TO zdb_update_script;
+SET ROLE zdb_update_script;
+CREATE INDEX idxupdate_script_unprivileged ON update_script_unprivileged USING zombodb ((update_script_unprivileged.*)) WITH (update_script='ctx._source.title = null;');
+RESET ROLE;
+DROP TABLE update_script_unprivileged;
+DROP ROLE zdb_update_script;
+
+CREATE INDEX idxupdate_script ON update_script USING zombodb ((update_script.*)) WITH (update_script_params='[1]');
+CREATE INDEX idxupdate_script ON update_script USING zombodb ((update_script.*)) WITH (update_script_params='{"XMAX": 1}');
+CREATE INDEX idxupdate_script ON update_script USING zombodb ((update_script.*)) WITH (update_script='ctx._source.update_count = (ctx._source.update_count == null ? 0 : ctx._source.update_count) + params.STEP;', update_script_params='{"STEP": 1}');
+
+INSERT INTO update_script (title) VALUES ('one'), ('two');
+UPDATE update_script SET title = 'uno' WHERE id = 1;
+SELECT id, title FROM update_script WHERE update_script ==> 'title:uno' ORDER BY id;
+SELECT (zdb.request('idxupdate_script', '_search?q=update_count:1&size=0')::jsonb)->'hits'->'total' AS updated;
+
+DROP TABLE update_script;
@@ -0,0 +1,14 @@
+CREATE TABLE uuid_mapping (
+    id serial8 not null primary key,
+    key uuid
+);
+
+CREATE INDEX idxuuid_mapping ON uuid_mapping USING zombodb ((uuid_mapping.*));
+SELECT (zdb.index_mapping('idxuuid_mapping')->'mappings'->'doc'->'properties'->'key')::jsonb;
+
+INSERT INTO uuid_mapping (key) VALUES ('9e5b6ec0-a0be-42c1-84ff-6a8cda415a20'), ('5e70a4d3-e66f-4a2d-b62f-c0a0ae880a39'), (NULL);
+SELECT id, key FROM uuid_mapping WHERE uuid_mapping ==> 'key:"9e5b6ec0-a0be-42c1-84ff-6a8cda415a20"' ORDER BY id;
+SELECT id, key FROM uuid_mapping WHERE uuid_mapping ==> dsl.term('key', '5E70A4D3-E66F-4A2D-B62F-C0A0AE880A39') ORDER BY id;
+SELECT id, key FROM uuid_mapping WHERE uuid_mapping ==> 'key:*' ORDER BY id;
+
+DROP TABLE uuid_mapping;
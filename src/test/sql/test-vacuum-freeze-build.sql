@@ -0,0 +1,13 @@
+CREATE TABLE vacuum_freeze_build (
+    id serial8 not null primary key,
+    title text
+);
+INSERT INTO vacuum_freeze_build (title) VALUES ('one'), ('two'), ('three');
+UPDATE vacuum_freeze_build SET title = 'updated two' WHERE id = 2;
+VACUUM FREEZE vacuum_freeze_build;
+
+CREATE INDEX idxvacuum_freeze_build ON vacuum_freeze_build USING zombodb ((vacuum_freeze_build.*));
+SELECT id, title FROM vacuum_freeze_build WHERE vacuum_freeze_build ==> 'title:two' ORDER BY id;
+SELECT count(*) FROM vacuum_freeze_build WHERE vacuum_freeze_build ==> 'id:*';
+
+DROP TABLE vacuum_freeze_build;
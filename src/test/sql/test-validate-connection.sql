@@ -0,0 +1,11 @@
+SELECT reachable, cluster_name IS NOT NULL AS has_name, version IS NOT NULL AS has_version, message FROM zdb.validate_connection(current_setting('zdb.default_elasticsearch_url'));
+SELECT reachable, cluster_name, version, message FROM zdb.validate_connection(current_setting('zdb.default_elasticsearch_url') || 'zdb_no_such_index');
+SELECT reachable, cluster_name, version, message LIKE 'Couldn''t connect to server%' AS unreachable FROM zdb.validate_connection('http://localhost:1/');
+
+-- it makes the server request whatever url it's given, so it's not for everyone
+DROP ROLE IF EXISTS zdb_validate_connection;
+CREATE ROLE zdb_validate_connection;
+SET ROLE zdb_validate_connection;
+SELECT reachable FROM zdb.validate_connection(current_setting('zdb.default_elasticsearch_url'));
+RESET ROLE;
+DROP ROLE zdb_validate_connection;
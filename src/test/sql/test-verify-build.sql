@@ -0,0 +1,25 @@
+CREATE TABLE verify_build (
+    id serial8 not null primary key,
+    title text
+);
+INSERT INTO verify_build (title) VALUES ('one'), ('two'), ('three');
+
+-- an ingest pipeline that quietly drops one of the documents it's sent
+SELECT zdb.request('idxevents', '/_ingest/pipeline/zdb_verify_build_drop', 'PUT', $${"processors": [{"drop": {"if": "ctx.title == 'two'"}}]}$$)::json->>'acknowledged' AS acknowledged;
+
+-- without zdb.verify_build the build doesn't notice
+CREATE INDEX idxverify_build ON verify_build USING zombodb ((verify_build.*)) WITH (index_settings='{"index.default_pipeline": "zdb_verify_build_drop"}');
+
+\set VERBOSITY terse
+SET zdb.verify_build TO on;
+REINDEX INDEX idxverify_build;
+SELECT zdb.count('idxverify_build', dsl.match_all());
+
+-- and when everything arrives there's nothing to say
+ALTER INDEX idxverify_build RESET (index_settings);
+REINDEX INDEX idxverify_build;
+SELECT zdb.count('idxverify_build', dsl.match_all());
+RESET zdb.verify_build;
+
+DROP TABLE verify_build;
+SELECT zdb.request('idxevents', '/_ingest/pipeline/zdb_verify_build_drop', 'DELETE')::json->>'acknowledged' AS acknowledged;
@@ -0,0 +1,17 @@
+CREATE TABLE wait_for_active_shards (
+    id serial8 not null primary key,
+    title text
+);
+
+CREATE INDEX idxwait_for_active_shards ON wait_for_active_shards USING zombodb ((wait_for_active_shards.*)) WITH (wait_for_active_shards='some');
+CREATE INDEX idxwait_for_active_shards ON wait_for_active_shards USING zombodb ((wait_for_active_shards.*)) WITH (wait_for_active_shards='0');
+CREATE INDEX idxwait_for_active_shards ON wait_for_active_shards USING zombodb ((wait_for_active_shards.*)) WITH (wait_for_active_shards='all');
+
+INSERT INTO wait_for_active_shards (title) VALUES ('one'), ('two');
+SELECT id, title FROM wait_for_active_shards WHERE wait_for_active_shards ==> 'title:two' ORDER BY id;
+
+ALTER INDEX idxwait_for_active_shards SET (wait_for_active_shards='1');
+INSERT INTO wait_for_active_shards (title) VALUES ('three');
+SELECT id, title FROM wait_for_active_shards WHERE wait_for_active_shards ==> 'title:three' ORDER BY id;
+
+DROP TABLE wait_for_active_shards;
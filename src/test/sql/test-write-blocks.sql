@@ -0,0 +1,18 @@
+CREATE TABLE write_blocks (
+    id serial8 not null primary key,
+    title text
+);
+INSERT INTO write_blocks (title) VALUES ('one');
+CREATE INDEX idxwrite_blocks ON write_blocks USING zombodb ((write_blocks.*)) WITH (on_error='skip');
+
+SELECT zdb.request('idxwrite_blocks', '_settings', 'PUT', '{"index.blocks.read_only_allow_delete": true}');
+\set VERBOSITY terse
+INSERT INTO write_blocks (title) VALUES ('two');
+\set VERBOSITY default
+SELECT count(*) FROM zdb.dead_letters WHERE index = 'idxwrite_blocks'::regclass;
+
+SELECT zdb.request('idxwrite_blocks', '_settings', 'PUT', '{"index.blocks.read_only_allow_delete": null}');
+INSERT INTO write_blocks (title) VALUES ('three');
+SELECT id, title FROM write_blocks WHERE write_blocks ==> 'title:(two OR three)' ORDER BY id;
+
+DROP TABLE write_blocks;
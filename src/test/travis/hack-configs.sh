@@ -6,6 +6,7 @@ cat << DONE >> /etc/postgresql/${PG}/main/postgresql.conf
 client_min_messages=warning
 autovacuum=off
 fsync=off
+shared_preload_libraries = 'zombodb.so'
 zdb.default_elasticsearch_url = 'http://localhost:9200/'
 zdb.log_level = LOG
 zdb.default_replicas = 0